@@ -1,14 +1,7 @@
 use dotenv::dotenv;
 use std::env;
 
-use tradingview::{
-    callback::Callbacks,
-    chart::ChartOptions,
-    pine_indicator::ScriptType,
-    socket::DataServer,
-    websocket::{WebSocket, WebSocketClient},
-    Interval, QuoteValue,
-};
+use tradingview::prelude::*;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -68,5 +61,6 @@ async fn main() -> anyhow::Result<()> {
 
     tokio::spawn(async move { websocket.subscribe().await });
 
-    loop {}
+    tokio::signal::ctrl_c().await?;
+    Ok(())
 }
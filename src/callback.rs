@@ -1,6 +1,8 @@
 use crate::{
     chart::{
-        models::{DataPoint, StudyResponseData, SymbolInfo},
+        models::{
+            DataPoint, IntervalDowngrade, SeriesLoadingProgress, StudyResponseData, SymbolInfo,
+        },
         ChartOptions, StudyOptions,
     },
     quote::models::QuoteValue,
@@ -9,10 +11,11 @@ use crate::{
 };
 use futures_util::{future::BoxFuture, Future};
 use serde_json::Value;
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 use tracing::{error, info};
 
 pub type AsyncCallback<'a, T> = Box<dyn (Fn(T) -> BoxFuture<'a, ()>) + Send + Sync + 'a>;
+type ChartDataCallback<'a> = Arc<AsyncCallback<'a, (ChartOptions, Vec<DataPoint>)>>;
 
 #[derive(Clone)]
 pub struct Callbacks<'a> {
@@ -21,7 +24,12 @@ pub struct Callbacks<'a> {
     pub(crate) on_study_data: Arc<AsyncCallback<'a, (StudyOptions, StudyResponseData)>>,
     pub(crate) on_error: Arc<AsyncCallback<'a, Error>>,
     pub(crate) on_symbol_info: Arc<AsyncCallback<'a, SymbolInfo>>,
+    pub(crate) on_series_loading: Arc<AsyncCallback<'a, SeriesLoadingProgress>>,
+    pub(crate) on_interval_downgrade: Arc<AsyncCallback<'a, IntervalDowngrade>>,
     pub(crate) on_other_event: Arc<AsyncCallback<'a, (TradingViewDataEvent, Vec<Value>)>>,
+    /// Chart data handlers for single series, keyed by series id, used instead of
+    /// `on_chart_data` for those series.
+    pub(crate) series_chart_data: HashMap<String, ChartDataCallback<'a>>,
 }
 
 impl Default for Callbacks<'_> {
@@ -43,14 +51,44 @@ impl Default for Callbacks<'_> {
             on_symbol_info: Arc::new(Box::new(|data| {
                 Box::pin(async move { info!("default callback logging && handling: {:?}", data) })
             })),
+            on_series_loading: Arc::new(Box::new(|data| {
+                Box::pin(async move { info!("default callback logging && handling: {:?}", data) })
+            })),
+            on_interval_downgrade: Arc::new(Box::new(|data| {
+                Box::pin(async move { info!("default callback logging && handling: {:?}", data) })
+            })),
             on_other_event: Arc::new(Box::new(|data| {
                 Box::pin(async move { info!("default callback logging && handling: {:?}", data) })
             })),
+            series_chart_data: HashMap::new(),
         }
     }
 }
 
 impl<'a> Callbacks<'a> {
+    /// Routes chart data of `series_id` (e.g. `"sds_1"`) to `f` instead of `on_chart_data`.
+    pub fn on_series_chart_data<Fut>(
+        mut self,
+        series_id: &str,
+        f: impl Fn((ChartOptions, Vec<DataPoint>)) -> Fut + Send + Sync + 'a,
+    ) -> Self
+    where
+        Fut: Future<Output = ()> + Send + 'a,
+    {
+        self.series_chart_data.insert(
+            series_id.to_string(),
+            Arc::new(Box::new(move |data| Box::pin(f(data)))),
+        );
+        self
+    }
+
+    /// The chart data handler of `series_id`, falling back to `on_chart_data`.
+    pub(crate) fn chart_data_for(&self, series_id: &str) -> &ChartDataCallback<'a> {
+        self.series_chart_data
+            .get(series_id)
+            .unwrap_or(&self.on_chart_data)
+    }
+
     pub fn on_chart_data<Fut>(
         mut self,
         f: impl Fn((ChartOptions, Vec<DataPoint>)) -> Fut + Send + Sync + 'a,
@@ -97,6 +135,31 @@ impl<'a> Callbacks<'a> {
         self
     }
 
+    /// Called when a series starts loading, e.g. to show a spinner until its data arrives.
+    pub fn on_series_loading<Fut>(
+        mut self,
+        f: impl Fn(SeriesLoadingProgress) -> Fut + Send + Sync + 'a,
+    ) -> Self
+    where
+        Fut: Future<Output = ()> + Send + 'a,
+    {
+        self.on_series_loading = Arc::new(Box::new(move |data| Box::pin(f(data))));
+        self
+    }
+
+    /// Called once per series when its first history load looks coarser than requested,
+    /// e.g. second intervals without the required subscription.
+    pub fn on_interval_downgrade<Fut>(
+        mut self,
+        f: impl Fn(IntervalDowngrade) -> Fut + Send + Sync + 'a,
+    ) -> Self
+    where
+        Fut: Future<Output = ()> + Send + 'a,
+    {
+        self.on_interval_downgrade = Arc::new(Box::new(move |data| Box::pin(f(data))));
+        self
+    }
+
     pub fn on_other_event<Fut>(
         mut self,
         f: impl Fn((TradingViewDataEvent, Vec<Value>)) -> Fut + Send + Sync + 'a,
@@ -0,0 +1,134 @@
+use std::sync::Arc;
+
+use arrow_array::{Array, ArrayRef, Float64Array, RecordBatch, TimestampSecondArray};
+use arrow_schema::{ArrowError, DataType, Field, Schema, SchemaRef, TimeUnit};
+
+use crate::{
+    chart::{
+        models::{ChartHistoricalData, DataPoint, OHLCV},
+        ChartOptions,
+    },
+    Result,
+};
+
+lazy_static::lazy_static! {
+    static ref OHLCV_SCHEMA: SchemaRef = Arc::new(Schema::new(vec![
+        Field::new(
+            "timestamp",
+            DataType::Timestamp(TimeUnit::Second, Some("UTC".into())),
+            false,
+        ),
+        Field::new("open", DataType::Float64, false),
+        Field::new("high", DataType::Float64, false),
+        Field::new("low", DataType::Float64, false),
+        Field::new("close", DataType::Float64, false),
+        Field::new("volume", DataType::Float64, false),
+    ]));
+}
+
+impl ChartHistoricalData {
+    /// Schema of [`ChartHistoricalData::to_arrow`]: a UTC second timestamp and five `Float64`
+    /// columns.
+    pub fn arrow_schema() -> SchemaRef {
+        OHLCV_SCHEMA.clone()
+    }
+
+    /// The bars as an Arrow `RecordBatch`, sorted by time with duplicates removed.
+    pub fn to_arrow(&self) -> Result<RecordBatch> {
+        let bars = self.to_ohlcv();
+        let column = |f: fn(&OHLCV) -> f64| -> ArrayRef {
+            Arc::new(Float64Array::from_iter_values(bars.iter().map(f)))
+        };
+        let timestamps = TimestampSecondArray::from_iter_values(bars.iter().map(|b| b.timestamp))
+            .with_timezone("UTC");
+        let batch = RecordBatch::try_new(
+            Self::arrow_schema(),
+            vec![
+                Arc::new(timestamps),
+                column(|b| b.open),
+                column(|b| b.high),
+                column(|b| b.low),
+                column(|b| b.close),
+                column(|b| b.volume),
+            ],
+        )?;
+        Ok(batch)
+    }
+
+    /// Rebuilds bars from a batch laid out like [`ChartHistoricalData::arrow_schema`]. Columns
+    /// are looked up by name; the result has default `ChartOptions`.
+    pub fn from_arrow(batch: &RecordBatch) -> Result<Self> {
+        fn column<'b, T: 'static>(batch: &'b RecordBatch, name: &str) -> Result<&'b T> {
+            let column = batch
+                .column_by_name(name)
+                .ok_or_else(|| ArrowError::SchemaError(format!("missing column {name}")))?;
+            if column.null_count() > 0 {
+                return Err(ArrowError::SchemaError(format!("column {name} has nulls")).into());
+            }
+            Ok(column.as_any().downcast_ref::<T>().ok_or_else(|| {
+                ArrowError::SchemaError(format!("column {name} has type {}", column.data_type()))
+            })?)
+        }
+        let timestamps = column::<TimestampSecondArray>(batch, "timestamp")?;
+        let values = ["open", "high", "low", "close", "volume"]
+            .map(|name| column::<Float64Array>(batch, name));
+        let [open, high, low, close, volume] = values;
+        let (open, high, low, close, volume) = (open?, high?, low?, close?, volume?);
+        let data = (0..batch.num_rows())
+            .map(|i| DataPoint {
+                index: i as i64,
+                value: vec![
+                    timestamps.value(i) as f64,
+                    open.value(i),
+                    high.value(i),
+                    low.value(i),
+                    close.value(i),
+                    volume.value(i),
+                ],
+            })
+            .collect();
+        Ok(ChartHistoricalData::new(ChartOptions::default(), data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_arrow_schema_and_rows() {
+        let bar = |ts: f64, close: f64| DataPoint {
+            index: 0,
+            value: vec![ts, close, close, close, close, 10.0],
+        };
+        let data = ChartHistoricalData::new(
+            ChartOptions::default(),
+            vec![
+                bar(120.0, 3.0),
+                bar(0.0, 1.0),
+                bar(60.0, 2.0),
+                bar(120.0, 3.5),
+            ],
+        );
+
+        let batch = data.to_arrow().unwrap();
+        assert_eq!(batch.schema(), ChartHistoricalData::arrow_schema());
+        assert_eq!(batch.num_columns(), 6);
+        assert_eq!(batch.num_rows(), 3);
+        assert_eq!(
+            batch.schema().field(0).data_type(),
+            &DataType::Timestamp(TimeUnit::Second, Some("UTC".into()))
+        );
+        let close = batch
+            .column(4)
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .unwrap();
+        assert_eq!(close.value(2), 3.5);
+
+        let restored = ChartHistoricalData::from_arrow(&batch).unwrap();
+        assert_eq!(restored.to_ohlcv(), data.to_ohlcv());
+        let projected = batch.project(&[0, 1, 2, 3, 4]).unwrap();
+        assert!(ChartHistoricalData::from_arrow(&projected).is_err());
+    }
+}
@@ -0,0 +1,181 @@
+use std::io::{BufRead, BufReader, Read, Write};
+
+use crate::{
+    chart::{
+        models::{ChartHistoricalData, DataPoint},
+        ChartOptions,
+    },
+    Error, Result,
+};
+
+const COLUMNS: [&str; 6] = ["timestamp", "open", "high", "low", "close", "volume"];
+
+/// Layout of the CSV read and written by [`ChartHistoricalData::read_csv`] and
+/// [`ChartHistoricalData::write_csv`]: one bar per line with the columns
+/// `timestamp,open,high,low,close,volume`, timestamps in unix seconds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CsvOptions {
+    pub delimiter: char,
+    pub header: bool,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: ',',
+            header: true,
+        }
+    }
+}
+
+impl CsvOptions {
+    pub fn delimiter(mut self, delimiter: char) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    pub fn header(mut self, header: bool) -> Self {
+        self.header = header;
+        self
+    }
+}
+
+impl ChartHistoricalData {
+    /// Writes the bars as CSV, sorted by time with duplicate timestamps removed.
+    pub fn write_csv<W: Write>(&self, mut writer: W, options: CsvOptions) -> Result<()> {
+        let delimiter = options.delimiter.to_string();
+        if options.header {
+            writeln!(writer, "{}", COLUMNS.join(&delimiter))?;
+        }
+        for bar in self.to_ohlcv() {
+            let fields =
+                [bar.open, bar.high, bar.low, bar.close, bar.volume].map(|v| v.to_string());
+            writeln!(
+                writer,
+                "{}{delimiter}{}",
+                bar.timestamp,
+                fields.join(&delimiter)
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Reads bars written by [`write_csv`](Self::write_csv). The header, when expected, must
+    /// name the six columns in order and every row must hold six numbers. The result has
+    /// default `ChartOptions`.
+    pub fn read_csv<R: Read>(reader: R, options: CsvOptions) -> Result<Self> {
+        let mut data = Vec::new();
+        let mut header_pending = options.header;
+        for (i, line) in BufReader::new(reader).lines().enumerate() {
+            let line = line?;
+            let line_number = i + 1;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let fields: Vec<&str> = line.split(options.delimiter).map(str::trim).collect();
+            let invalid = |reason: String| Error::InvalidCsv {
+                line: line_number,
+                reason,
+            };
+            if header_pending {
+                header_pending = false;
+                let matches = fields.len() == COLUMNS.len()
+                    && fields
+                        .iter()
+                        .zip(COLUMNS)
+                        .all(|(field, column)| field.eq_ignore_ascii_case(column));
+                if !matches {
+                    return Err(invalid(format!(
+                        "expected header {}",
+                        COLUMNS.join(&options.delimiter.to_string())
+                    )));
+                }
+                continue;
+            }
+            if fields.len() != COLUMNS.len() {
+                return Err(invalid(format!(
+                    "expected {} columns, found {}",
+                    COLUMNS.len(),
+                    fields.len()
+                )));
+            }
+            let value = fields
+                .iter()
+                .zip(COLUMNS)
+                .map(|(field, column)| {
+                    field
+                        .parse::<f64>()
+                        .map_err(|_| invalid(format!("{column} is not a number: {field:?}")))
+                })
+                .collect::<Result<Vec<f64>>>()?;
+            data.push(DataPoint {
+                index: data.len() as i64,
+                value,
+            });
+        }
+        Ok(ChartHistoricalData::new(ChartOptions::default(), data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> ChartHistoricalData {
+        let bar = |index: i64, timestamp: f64, close: f64| DataPoint {
+            index,
+            value: vec![
+                timestamp,
+                close - 0.5,
+                close + 1.25,
+                close - 1.0,
+                close,
+                1234.5,
+            ],
+        };
+        ChartHistoricalData::new(
+            ChartOptions::default(),
+            vec![
+                bar(0, 1_700_000_000.0, 101.1),
+                bar(1, 1_700_000_060.0, 0.1 + 0.2),
+                bar(2, 1_700_000_120.0, 99.875),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_csv_round_trip() {
+        let data = sample();
+        for options in [
+            CsvOptions::default(),
+            CsvOptions::default().delimiter(';').header(false),
+        ] {
+            let mut buf = Vec::new();
+            data.write_csv(&mut buf, options).unwrap();
+            let read = ChartHistoricalData::read_csv(buf.as_slice(), options).unwrap();
+            assert_eq!(read.data, data.data);
+        }
+    }
+
+    #[test]
+    fn test_read_csv_validates_input() {
+        let read = |csv: &str| ChartHistoricalData::read_csv(csv.as_bytes(), CsvOptions::default());
+
+        assert!(matches!(
+            read("time,open,high,low,close,volume\n"),
+            Err(Error::InvalidCsv { line: 1, .. })
+        ));
+        assert!(matches!(
+            read("timestamp,open,high,low,close,volume\n1,2,3,4,5\n"),
+            Err(Error::InvalidCsv { line: 2, .. })
+        ));
+        assert!(matches!(
+            read("timestamp,open,high,low,close,volume\n1,2,3,x,5,6\n"),
+            Err(Error::InvalidCsv { line: 2, .. })
+        ));
+        assert!(read("timestamp,open,high,low,close,volume\n")
+            .unwrap()
+            .data
+            .is_empty());
+    }
+}
@@ -1,13 +1,44 @@
-use iso_currency::Currency;
+use serde::{Deserialize, Serialize};
 
-use crate::models::{pine_indicator::ScriptType, Interval, MarketAdjustment, SessionType};
+use crate::models::{
+    pine_indicator::ScriptType, CurrencyOverride, Interval, MarketAdjustment, Range, SessionType,
+};
 
+#[cfg(feature = "arrow")]
+mod arrow;
+mod csv;
 pub mod models;
 pub(crate) mod options;
+#[cfg(feature = "parquet")]
+mod parquet;
 pub mod study;
 pub(crate) mod utils;
 
-#[derive(Default, Debug, Clone)]
+pub use csv::CsvOptions;
+pub use utils::{
+    dedup_bars, detect_downgrade, drop_unclosed_bar, infer_interval, MIN_BARS_FOR_DOWNGRADE,
+};
+
+/// Bars requested by [`ChartOptions::new`] unless overridden with
+/// [`ChartOptions::bar_count`].
+pub const DEFAULT_BAR_COUNT: u64 = 50_000;
+
+/// Largest number of bars requested from the server at once; bigger `bar_count`s are
+/// fetched as an initial window followed by `request_more_data` chunks.
+pub const MAX_BARS_PER_REQUEST: u64 = 20_000;
+
+/// Which bars to load, see [`ChartOptions::from_history`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistorySpec {
+    /// The latest `n` bars.
+    LastN(u64),
+    /// A period preset counted back from the latest bar.
+    Range(Range),
+    /// Bars between two unix timestamps in seconds.
+    Between { from: u64, to: u64 },
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ChartOptions {
     // Required
     pub symbol: String,
@@ -21,12 +52,18 @@ pub struct ChartOptions {
     pub(crate) replay_from: i64,
     pub(crate) replay_session: Option<String>,
     pub(crate) adjustment: Option<MarketAdjustment>,
-    pub(crate) currency: Option<Currency>,
+    pub(crate) currency: Option<CurrencyOverride>,
     pub(crate) session_type: Option<SessionType>,
     pub study_config: Option<StudyOptions>,
+    #[serde(default)]
+    pub(crate) strict: bool,
+    #[serde(default)]
+    pub(crate) exclude_unclosed: bool,
+    #[serde(default)]
+    pub(crate) force_new: bool,
 }
 
-#[derive(Default, Debug, Clone)]
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct StudyOptions {
     pub script_id: String,
     pub script_version: String,
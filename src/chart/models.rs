@@ -1,5 +1,13 @@
+use iso_currency::Currency;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::{collections::HashSet, ops::Deref};
+
+use crate::{
+    chart::{dedup_bars, detect_downgrade, infer_interval, ChartOptions},
+    models::{Interval, SymbolType},
+    Error, Result,
+};
 
 pub enum ChartType {
     HeikinAshi,
@@ -49,18 +57,377 @@ pub struct GraphicDataResponse {
     pub indexes: Value,
 }
 
+/// One bar or study row, serialized in the server's `{"i": index, "v": [values]}` form.
 #[cfg_attr(not(feature = "protobuf"), derive(Debug, Default))]
 #[cfg_attr(feature = "protobuf", derive(prost::Message))]
 #[derive(Clone, Deserialize, Serialize, PartialEq)]
 pub struct DataPoint {
     #[cfg_attr(feature = "protobuf", prost(int64, tag = "1"))]
-    #[serde(rename(deserialize = "i"))]
+    #[serde(rename = "i")]
     pub index: i64,
     #[cfg_attr(feature = "protobuf", prost(double, repeated, tag = "2"))]
-    #[serde(rename(deserialize = "v"))]
+    #[serde(rename = "v")]
     pub value: Vec<f64>,
 }
 
+impl DataPoint {
+    /// Bar open time as sent by TradingView, the first element of `value`, in Unix seconds.
+    /// Same as [`DataPoint::timestamp_secs`].
+    pub fn timestamp(&self) -> i64 {
+        self.timestamp_secs()
+    }
+
+    /// Bar open time in Unix seconds, the unit TradingView uses for chart bars.
+    pub fn timestamp_secs(&self) -> i64 {
+        self.value.first().copied().unwrap_or_default() as i64
+    }
+
+    /// Bar open time in Unix milliseconds.
+    pub fn timestamp_millis(&self) -> i64 {
+        (self.value.first().copied().unwrap_or_default() * 1000.0) as i64
+    }
+
+    pub fn open(&self) -> f64 {
+        self.value.get(1).copied().unwrap_or(f64::NAN)
+    }
+
+    pub fn high(&self) -> f64 {
+        self.value.get(2).copied().unwrap_or(f64::NAN)
+    }
+
+    pub fn low(&self) -> f64 {
+        self.value.get(3).copied().unwrap_or(f64::NAN)
+    }
+
+    pub fn close(&self) -> f64 {
+        self.value.get(4).copied().unwrap_or(f64::NAN)
+    }
+
+    /// Missing for symbols without volume (indices, some forex pairs), reported as `0.0`.
+    pub fn volume(&self) -> f64 {
+        self.value.get(5).copied().unwrap_or_default()
+    }
+}
+
+/// A bar with named fields, for consumers that do not want to index `DataPoint::value`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct OHLCV {
+    pub timestamp: i64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: f64,
+}
+
+impl From<&DataPoint> for OHLCV {
+    fn from(point: &DataPoint) -> Self {
+        OHLCV {
+            timestamp: point.timestamp(),
+            open: point.open(),
+            high: point.high(),
+            low: point.low(),
+            close: point.close(),
+            volume: point.volume(),
+        }
+    }
+}
+
+impl From<DataPoint> for OHLCV {
+    fn from(point: DataPoint) -> Self {
+        OHLCV::from(&point)
+    }
+}
+
+/// The bars of a single series together with the options they were requested with.
+///
+/// Bars are `[time, open, high, low, close, volume]` as sent by TradingView; run
+/// [`dedup_bars`](crate::chart::dedup_bars) first if they were collected from several updates.
+#[derive(Debug, Clone, Default)]
+pub struct ChartHistoricalData {
+    pub options: ChartOptions,
+    pub data: Vec<DataPoint>,
+}
+
+/// What is wrong with a bar flagged by [`ChartHistoricalData::validate_bars`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BarIssueKind {
+    HighBelowLow,
+    /// An open, high, low or close that is zero, negative or missing.
+    NonPositivePrice,
+    CloseOutOfRange,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BarIssue {
+    /// Position of the bar in `ChartHistoricalData::data`.
+    pub position: usize,
+    pub timestamp: i64,
+    pub kind: BarIssueKind,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct BarStats {
+    pub mean: f64,
+    /// Sample standard deviation, `0.0` with fewer than two values.
+    pub std: f64,
+    pub min: f64,
+    pub max: f64,
+    pub n: usize,
+}
+
+impl BarStats {
+    /// Computes the statistics of `values`. An empty slice yields `BarStats::default()`.
+    pub fn from_values(values: &[f64]) -> Self {
+        let n = values.len();
+        if n == 0 {
+            return BarStats::default();
+        }
+        let mean = values.iter().sum::<f64>() / n as f64;
+        let std = if n > 1 {
+            let var = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1) as f64;
+            var.sqrt()
+        } else {
+            0.0
+        };
+        BarStats {
+            mean,
+            std,
+            min: values.iter().copied().fold(f64::INFINITY, f64::min),
+            max: values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            n,
+        }
+    }
+}
+
+impl ChartHistoricalData {
+    pub fn new(options: ChartOptions, data: Vec<DataPoint>) -> Self {
+        Self { options, data }
+    }
+
+    /// Close-to-close log returns in bar order, one less than the number of bars.
+    /// Fewer than two bars yields an empty vec.
+    pub fn log_returns(&self) -> Vec<f64> {
+        self.data
+            .windows(2)
+            .map(|w| (w[1].close() / w[0].close()).ln())
+            .collect()
+    }
+
+    /// Merges a live update into the sorted bars: a bar with the same timestamp is replaced
+    /// (the still-forming last bar in the common case), otherwise the bar is inserted at its
+    /// sorted position, which for a live feed means appended.
+    pub fn apply_update(&mut self, point: DataPoint) {
+        let timestamp = point.timestamp();
+        if self
+            .data
+            .last()
+            .is_none_or(|last| last.timestamp() < timestamp)
+        {
+            self.data.push(point);
+            return;
+        }
+        match self
+            .data
+            .binary_search_by_key(&timestamp, DataPoint::timestamp)
+        {
+            Ok(i) => self.data[i] = point,
+            Err(i) => self.data.insert(i, point),
+        }
+    }
+
+    /// The bars as `OHLCV`, sorted ascending by time with duplicate timestamps removed.
+    pub fn to_ohlcv(&self) -> Vec<OHLCV> {
+        let mut data = self.data.clone();
+        dedup_bars(&mut data);
+        data.into_iter().map(OHLCV::from).collect()
+    }
+
+    /// Heikin-Ashi candles over [`to_ohlcv`](Self::to_ohlcv). The first candle is seeded with
+    /// the midpoint of its bar's open and close; each later open is the midpoint of the
+    /// previous Heikin-Ashi open and close. Timestamps and volumes are kept as is.
+    pub fn heikin_ashi(&self) -> Vec<OHLCV> {
+        let mut candles: Vec<OHLCV> = Vec::with_capacity(self.data.len());
+        for bar in self.to_ohlcv() {
+            let close = (bar.open + bar.high + bar.low + bar.close) / 4.0;
+            let open = match candles.last() {
+                Some(prev) => (prev.open + prev.close) / 2.0,
+                None => (bar.open + bar.close) / 2.0,
+            };
+            candles.push(OHLCV {
+                timestamp: bar.timestamp,
+                open,
+                high: bar.high.max(open).max(close),
+                low: bar.low.min(open).min(close),
+                close,
+                volume: bar.volume,
+            });
+        }
+        candles
+    }
+
+    /// The interval the bars are spaced at, see [`infer_interval`](crate::chart::infer_interval).
+    pub fn actual_interval(&self) -> Option<Interval> {
+        infer_interval(&self.data)
+    }
+
+    /// Whether the server sent coarser bars than `options.interval` asked for, as it does for
+    /// second intervals without the required subscription. A guess from the bar spacing with
+    /// the limits of [`detect_downgrade`](crate::chart::detect_downgrade).
+    pub fn is_downgraded(&self) -> bool {
+        detect_downgrade(&self.data, self.options.interval).is_some()
+    }
+
+    /// Statistics over the close prices.
+    pub fn summary_stats(&self) -> BarStats {
+        let closes: Vec<f64> = self.data.iter().map(DataPoint::close).collect();
+        BarStats::from_values(&closes)
+    }
+
+    /// Flags bars with a non-positive or missing price, a high below the low, or a close
+    /// outside `[low, high]`, in bar order. A bar can be flagged for more than one issue.
+    pub fn validate_bars(&self) -> Vec<BarIssue> {
+        let mut issues = Vec::new();
+        for (position, bar) in self.data.iter().enumerate() {
+            let mut flag = |kind| {
+                issues.push(BarIssue {
+                    position,
+                    timestamp: bar.timestamp(),
+                    kind,
+                })
+            };
+            let (open, high, low, close) = (bar.open(), bar.high(), bar.low(), bar.close());
+            if [open, high, low, close]
+                .iter()
+                .any(|p| p.is_nan() || *p <= 0.0)
+            {
+                flag(BarIssueKind::NonPositivePrice);
+            }
+            if high < low {
+                flag(BarIssueKind::HighBelowLow);
+            }
+            if close < low || close > high {
+                flag(BarIssueKind::CloseOutOfRange);
+            }
+        }
+        issues
+    }
+
+    /// Removes every bar flagged by [`validate_bars`](Self::validate_bars) and returns how
+    /// many were removed.
+    pub fn drop_invalid(&mut self) -> usize {
+        let invalid: HashSet<usize> = self
+            .validate_bars()
+            .into_iter()
+            .map(|issue| issue.position)
+            .collect();
+        let mut position = 0;
+        self.data.retain(|_| {
+            position += 1;
+            !invalid.contains(&(position - 1))
+        });
+        invalid.len()
+    }
+
+    /// Converts open, high, low and close from `from` to `to` with the closes of `fx`, an
+    /// FX series quoted as `FROMTO` (multiplied) or `TOFROM` (divided), e.g. `FX:EURUSD` to
+    /// convert EUR prices to USD. Each bar uses the latest FX close at or before its time,
+    /// so gaps in `fx` are forward-filled; bars older than the first FX bar are an error.
+    /// Volume is kept and `options.currency` is set to `to`.
+    pub fn convert_currency(
+        &self,
+        from: Currency,
+        to: Currency,
+        fx: &ChartHistoricalData,
+    ) -> Result<ChartHistoricalData> {
+        let mut options = self.options.clone().currency(to);
+        if from == to {
+            options.currency = self.options.currency.clone();
+            return Ok(ChartHistoricalData::new(options, self.data.clone()));
+        }
+        let pair = fx
+            .options
+            .symbol
+            .rsplit(':')
+            .next()
+            .unwrap_or_default()
+            .to_uppercase();
+        let invert = if pair == format!("{}{}", from.code(), to.code()) {
+            false
+        } else if pair == format!("{}{}", to.code(), from.code()) {
+            true
+        } else {
+            return Err(Error::CurrencyConversion(format!(
+                "{} does not quote {} against {}",
+                fx.options.symbol,
+                from.code(),
+                to.code()
+            )));
+        };
+
+        let rates = fx.to_ohlcv();
+        let data = self
+            .data
+            .iter()
+            .map(|bar| {
+                let timestamp = bar.timestamp();
+                let i = rates.partition_point(|rate| rate.timestamp <= timestamp);
+                let rate = match i.checked_sub(1).map(|i| rates[i].close) {
+                    Some(rate) if rate > 0.0 => rate,
+                    _ => {
+                        return Err(Error::CurrencyConversion(format!(
+                            "no {} rate at or before {}",
+                            pair, timestamp
+                        )))
+                    }
+                };
+                let factor = if invert { 1.0 / rate } else { rate };
+                let mut value = bar.value.clone();
+                for price in value.iter_mut().skip(1).take(4) {
+                    *price *= factor;
+                }
+                Ok(DataPoint {
+                    index: bar.index,
+                    value,
+                })
+            })
+            .collect::<Result<Vec<DataPoint>>>()?;
+        Ok(ChartHistoricalData::new(options, data))
+    }
+}
+
+/// Read-only slice access to the bars, e.g. `data[0]` or `data.len()`.
+impl Deref for ChartHistoricalData {
+    type Target = [DataPoint];
+
+    fn deref(&self) -> &Self::Target {
+        &self.data
+    }
+}
+
+impl<'a> IntoIterator for &'a ChartHistoricalData {
+    type Item = &'a DataPoint;
+    type IntoIter = std::slice::Iter<'a, DataPoint>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.data.iter()
+    }
+}
+
+impl IntoIterator for ChartHistoricalData {
+    type Item = DataPoint;
+    type IntoIter = std::vec::IntoIter<DataPoint>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.data.into_iter()
+    }
+}
+
+/// Server-side id of a replay instance, announced by `replay_instance_id` after a replay
+/// session is created.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ReplayInstanceId(pub String);
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct ChartDataChanges {
     pub changes: Vec<f64>,
@@ -70,6 +437,41 @@ pub struct ChartDataChanges {
     pub zoffset: i64,
 }
 
+/// A series started loading bars, parsed from `series_loading`
+/// (`[chart_session, series_id, turnaround]`).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct SeriesLoadingProgress {
+    pub series_id: String,
+    /// Always `None`: `series_loading` carries no bar count, the bars arrive with the
+    /// following `timescale_update` and `series_completed` ends the load.
+    pub loaded: Option<u64>,
+    /// Bars this client asked for with `ChartOptions::bar_count`, not a figure from the
+    /// server. `None` for series the client does not know.
+    pub requested: Option<u64>,
+}
+
+impl SeriesLoadingProgress {
+    /// Parses the message params, `None` without a series id. `requested` is left unset.
+    pub fn from_message(message: &[Value]) -> Option<Self> {
+        Some(SeriesLoadingProgress {
+            series_id: message.get(1)?.as_str()?.to_string(),
+            loaded: None,
+            requested: None,
+        })
+    }
+}
+
+/// A series whose bars are coarser than its requested interval, see
+/// [`detect_downgrade`](crate::chart::detect_downgrade).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IntervalDowngrade {
+    pub series_id: String,
+    pub symbol: String,
+    pub requested: Interval,
+    /// Inferred from the bar spacing.
+    pub actual: Interval,
+}
+
 #[cfg_attr(not(feature = "protobuf"), derive(Debug, Default))]
 #[cfg_attr(feature = "protobuf", derive(prost::Message))]
 #[derive(Clone, PartialEq, Serialize, Hash)]
@@ -88,7 +490,7 @@ pub struct SeriesCompletedMessage {
 #[serde(rename_all = "camelCase")]
 pub struct SymbolInfo {
     #[cfg_attr(feature = "protobuf", prost(string, tag = "1"))]
-    #[serde(rename(deserialize = "pro_name"), default)]
+    #[serde(rename = "pro_name", alias = "id", default)]
     pub id: String,
     #[cfg_attr(feature = "protobuf", prost(string, tag = "2"))]
     #[serde(default)]
@@ -117,7 +519,7 @@ pub struct SymbolInfo {
     #[serde(rename = "currency_code", default)]
     pub currency_code: String,
     #[cfg_attr(feature = "protobuf", prost(string, tag = "11"))]
-    #[serde(default)]
+    #[serde(rename = "session_holidays", alias = "sessionHolidays", default)]
     pub session_holidays: String,
     #[cfg_attr(feature = "protobuf", prost(message, repeated, tag = "12"))]
     #[serde(default)]
@@ -126,7 +528,7 @@ pub struct SymbolInfo {
     #[serde(default)]
     pub timezone: String,
     #[cfg_attr(feature = "protobuf", prost(string, tag = "14"))]
-    #[serde(rename(deserialize = "type"), default)]
+    #[serde(rename = "type", alias = "marketType", default)]
     pub market_type: String,
     #[cfg_attr(feature = "protobuf", prost(string, repeated, tag = "15"))]
     #[serde(default)]
@@ -136,6 +538,22 @@ pub struct SymbolInfo {
     pub aliases: Vec<String>,
 }
 
+impl SymbolInfo {
+    /// Quote currency, `None` when `currency_code` is not an ISO 4217 code (e.g. `USDT`).
+    pub fn currency(&self) -> Option<Currency> {
+        Currency::from_code(&self.currency_code)
+    }
+
+    /// Instrument type parsed from `market_type`, unknown types map to `SymbolType::Undefined`.
+    pub fn symbol_type(&self) -> SymbolType {
+        SymbolType::from(self.market_type.as_str())
+    }
+
+    pub fn exchange(&self) -> &str {
+        &self.exchange
+    }
+}
+
 #[cfg_attr(not(feature = "protobuf"), derive(Debug, Default))]
 #[cfg_attr(feature = "protobuf", derive(prost::Message))]
 #[derive(Clone, PartialEq, Serialize, Deserialize, Hash)]
@@ -150,6 +568,329 @@ pub struct Subsession {
     #[cfg_attr(feature = "protobuf", prost(string, tag = "4"))]
     pub session: String,
     #[cfg_attr(feature = "protobuf", prost(string, tag = "5"))]
-    #[serde(rename(deserialize = "session-display"))]
+    #[serde(rename = "session-display", alias = "sessionDisplay")]
     pub session_display: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bar(timestamp: f64, open: f64, high: f64, low: f64, close: f64) -> DataPoint {
+        DataPoint {
+            index: 0,
+            value: vec![timestamp, open, high, low, close, 100.0],
+        }
+    }
+
+    fn closes(closes: &[f64]) -> ChartHistoricalData {
+        let data = closes
+            .iter()
+            .enumerate()
+            .map(|(i, c)| bar(i as f64 * 60.0, *c, *c, *c, *c))
+            .collect();
+        ChartHistoricalData::new(ChartOptions::default(), data)
+    }
+
+    #[test]
+    fn test_symbol_info_typed_accessors() {
+        let payload = serde_json::json!({
+            "pro_name": "NASDAQ:AAPL",
+            "name": "AAPL",
+            "description": "Apple Inc.",
+            "exchange": "NASDAQ",
+            "listed_exchange": "NASDAQ",
+            "type": "stock",
+            "currency_code": "USD",
+            "timezone": "America/New_York"
+        });
+        let info = SymbolInfo::deserialize(&payload).unwrap();
+        assert_eq!(info.currency(), Some(Currency::USD));
+        assert_eq!(info.symbol_type(), SymbolType::Stock);
+        assert_eq!(info.exchange(), "NASDAQ");
+
+        let payload = serde_json::json!({
+            "exchange": "BINANCE",
+            "type": "crypto",
+            "currency_code": "USDT"
+        });
+        let info = SymbolInfo::deserialize(&payload).unwrap();
+        assert_eq!(info.currency(), None);
+        assert_eq!(info.symbol_type(), SymbolType::Crypto);
+    }
+
+    #[test]
+    fn test_symbol_info_serde_round_trip() {
+        let path = std::env::current_dir()
+            .unwrap()
+            .join("tests/data/symbol_info.json");
+        let payload: Value = serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+
+        let info = SymbolInfo::deserialize(&payload).unwrap();
+        assert_eq!(info.id, "SP:SPX");
+        assert_eq!(info.market_type, "index");
+        assert!(!info.session_holidays.is_empty());
+        assert_eq!(info.subsessions[0].session_display, "0930-1610");
+
+        let serialized = serde_json::to_value(&info).unwrap();
+        assert_eq!(serialized["pro_name"], payload["pro_name"]);
+        assert_eq!(serialized["type"], payload["type"]);
+        assert_eq!(serialized["session_holidays"], payload["session_holidays"]);
+
+        let round_tripped = SymbolInfo::deserialize(&serialized).unwrap();
+        assert_eq!(round_tripped, info);
+        assert_eq!(serde_json::to_value(&round_tripped).unwrap(), serialized);
+    }
+
+    #[test]
+    fn test_apply_update_replaces_last_bar() {
+        let mut data = closes(&[100.0, 101.0]);
+        data.apply_update(bar(60.0, 101.0, 103.0, 100.5, 102.5));
+
+        assert_eq!(data.data.len(), 2);
+        assert_eq!(data.data[1].close(), 102.5);
+        assert_eq!(data.data[1].high(), 103.0);
+    }
+
+    #[test]
+    fn test_apply_update_appends_new_bar() {
+        let mut data = closes(&[100.0, 101.0]);
+        data.apply_update(bar(120.0, 102.0, 102.0, 102.0, 102.0));
+        assert_eq!(data.data.len(), 3);
+        assert_eq!(data.data[2].timestamp(), 120);
+
+        data.apply_update(bar(90.0, 1.0, 1.0, 1.0, 1.0));
+        let timestamps: Vec<i64> = data.data.iter().map(DataPoint::timestamp).collect();
+        assert_eq!(timestamps, vec![0, 60, 90, 120]);
+
+        let mut empty = closes(&[]);
+        empty.apply_update(bar(0.0, 1.0, 1.0, 1.0, 1.0));
+        assert_eq!(empty.data.len(), 1);
+    }
+
+    #[test]
+    fn test_log_returns() {
+        let data = closes(&[100.0, 110.0, 99.0]);
+        let returns = data.log_returns();
+        assert_eq!(returns.len(), 2);
+        assert!((returns[0] - 0.095_310_18).abs() < 1e-6);
+        assert!((returns[1] - -0.105_360_52).abs() < 1e-6);
+
+        assert!(closes(&[100.0]).log_returns().is_empty());
+        assert!(closes(&[]).log_returns().is_empty());
+    }
+
+    #[test]
+    fn test_summary_stats() {
+        let stats = closes(&[2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0]).summary_stats();
+        assert_eq!(stats.n, 8);
+        assert_eq!(stats.mean, 5.0);
+        assert!((stats.std - 2.138_089_9).abs() < 1e-6);
+        assert_eq!(stats.min, 2.0);
+        assert_eq!(stats.max, 9.0);
+
+        let single = closes(&[3.0]).summary_stats();
+        assert_eq!((single.n, single.mean, single.std), (1, 3.0, 0.0));
+        assert_eq!(closes(&[]).summary_stats(), BarStats::default());
+    }
+
+    #[test]
+    fn test_to_ohlcv_sorted_and_deduped() {
+        let data = ChartHistoricalData::new(
+            ChartOptions::default(),
+            vec![
+                bar(120.0, 3.0, 3.0, 3.0, 3.0),
+                bar(0.0, 1.0, 1.0, 1.0, 1.0),
+                bar(60.0, 2.0, 2.5, 1.5, 2.0),
+                bar(120.0, 3.0, 3.5, 3.0, 3.2),
+            ],
+        );
+        let ohlcv = data.to_ohlcv();
+        assert_eq!(ohlcv.len(), 3);
+        assert!(ohlcv.windows(2).all(|w| w[0].timestamp < w[1].timestamp));
+        assert_eq!(
+            ohlcv[1],
+            OHLCV {
+                timestamp: 60,
+                open: 2.0,
+                high: 2.5,
+                low: 1.5,
+                close: 2.0,
+                volume: 100.0,
+            }
+        );
+        assert_eq!(ohlcv[2].close, 3.2);
+    }
+
+    #[test]
+    fn test_data_point_timestamp_units() {
+        let point = bar(1_700_000_000.0, 1.0, 1.0, 1.0, 1.0);
+        assert_eq!(point.timestamp(), 1_700_000_000);
+        assert_eq!(point.timestamp_secs(), 1_700_000_000);
+        assert_eq!(point.timestamp_millis(), 1_700_000_000_000);
+        assert_eq!(point.timestamp_millis() / 1000, point.timestamp_secs());
+    }
+
+    #[test]
+    fn test_heikin_ashi() {
+        let data = ChartHistoricalData::new(
+            ChartOptions::default(),
+            vec![
+                bar(60.0, 11.0, 14.0, 10.0, 13.0),
+                bar(0.0, 10.0, 12.0, 9.0, 11.0),
+                bar(120.0, 13.0, 13.0, 11.0, 12.0),
+            ],
+        );
+        let candle = |timestamp, open, high, low, close| OHLCV {
+            timestamp,
+            open,
+            high,
+            low,
+            close,
+            volume: 100.0,
+        };
+        assert_eq!(
+            data.heikin_ashi(),
+            vec![
+                candle(0, 10.5, 12.0, 9.0, 10.5),
+                candle(60, 10.5, 14.0, 10.0, 12.0),
+                candle(120, 11.25, 13.0, 11.0, 12.25),
+            ]
+        );
+        assert!(closes(&[]).heikin_ashi().is_empty());
+    }
+
+    #[test]
+    fn test_data_point_wire_format() {
+        let raw = r#"{"i":0,"v":[1685633880.0,33019.3,33025.31,33018.84,33021.84,638315.0]}"#;
+        let point: DataPoint = serde_json::from_str(raw).unwrap();
+        assert_eq!(point.index, 0);
+        assert_eq!(point.close(), 33021.84);
+        assert_eq!(serde_json::to_string(&point).unwrap(), raw);
+    }
+
+    #[test]
+    fn test_actual_interval_and_downgrade() {
+        let mut data = closes(&[1.0; 12]);
+        assert_eq!(data.actual_interval(), Some(Interval::OneMinute));
+        data.options.interval = Interval::OneSecond;
+        assert!(data.is_downgraded());
+        data.options.interval = Interval::OneMinute;
+        assert!(!data.is_downgraded());
+        assert_eq!(closes(&[1.0]).actual_interval(), None);
+
+        // too few bars to tell a downgrade from a sparse series
+        let mut few = closes(&[1.0, 2.0, 3.0]);
+        few.options.interval = Interval::OneSecond;
+        assert!(!few.is_downgraded());
+
+        let monthly = ChartHistoricalData::new(
+            ChartOptions::default(),
+            [0.0, 28.0, 59.0]
+                .iter()
+                .map(|day| bar(day * 86_400.0, 1.0, 1.0, 1.0, 1.0))
+                .collect(),
+        );
+        assert_eq!(monthly.actual_interval(), Some(Interval::Monthly));
+    }
+
+    #[test]
+    fn test_validate_bars_flags_malformed_bars() {
+        let mut data = ChartHistoricalData::new(
+            ChartOptions::default(),
+            vec![
+                bar(0.0, 10.0, 12.0, 9.0, 11.0),
+                bar(60.0, 10.0, 9.0, 12.0, 10.5),
+                bar(120.0, 0.0, 12.0, 9.0, 11.0),
+                bar(180.0, 10.0, 12.0, 9.0, 13.0),
+                bar(240.0, 10.0, 12.0, -1.0, 11.0),
+                DataPoint {
+                    index: 5,
+                    value: vec![300.0, 10.0],
+                },
+                bar(360.0, 11.0, 11.0, 11.0, 11.0),
+            ],
+        );
+
+        let issues: Vec<(usize, BarIssueKind)> = data
+            .validate_bars()
+            .into_iter()
+            .map(|issue| (issue.position, issue.kind))
+            .collect();
+        assert_eq!(
+            issues,
+            vec![
+                (1, BarIssueKind::HighBelowLow),
+                (1, BarIssueKind::CloseOutOfRange),
+                (2, BarIssueKind::NonPositivePrice),
+                (3, BarIssueKind::CloseOutOfRange),
+                (4, BarIssueKind::NonPositivePrice),
+                (5, BarIssueKind::NonPositivePrice),
+            ]
+        );
+        assert_eq!(data.validate_bars()[0].timestamp, 60);
+
+        assert_eq!(data.drop_invalid(), 5);
+        let timestamps: Vec<i64> = data.data.iter().map(DataPoint::timestamp).collect();
+        assert_eq!(timestamps, vec![0, 360]);
+        assert!(data.validate_bars().is_empty());
+    }
+
+    #[test]
+    fn test_iterate_and_index_bars() {
+        let data = closes(&[10.0, 11.0, 12.0]);
+        assert_eq!(data.len(), 3);
+        assert_eq!(data[1].close(), 11.0);
+        assert_eq!(data.last().map(DataPoint::close), Some(12.0));
+
+        let mut sum = 0.0;
+        for bar in &data {
+            sum += bar.close();
+        }
+        assert_eq!(sum, 33.0);
+
+        let timestamps: Vec<i64> = data.into_iter().map(|bar| bar.timestamp()).collect();
+        assert_eq!(timestamps, vec![0, 60, 120]);
+    }
+
+    #[test]
+    fn test_convert_currency_with_flat_rate() {
+        let prices = ChartHistoricalData::new(
+            ChartOptions::new("XETR:SAP", Interval::Daily),
+            vec![
+                bar(0.0, 10.0, 12.0, 8.0, 11.0),
+                bar(86_400.0, 11.0, 13.0, 10.0, 12.0),
+                bar(172_800.0, 12.0, 12.5, 11.0, 12.5),
+            ],
+        );
+        // the second day has no FX bar and reuses the first one
+        let rate = |timestamp: f64| bar(timestamp, 1.25, 1.25, 1.25, 1.25);
+        let eurusd = ChartHistoricalData::new(
+            ChartOptions::new("FX:EURUSD", Interval::Daily),
+            vec![rate(0.0), rate(172_800.0)],
+        );
+
+        let usd = prices
+            .convert_currency(Currency::EUR, Currency::USD, &eurusd)
+            .unwrap();
+        let closes: Vec<f64> = usd.iter().map(DataPoint::close).collect();
+        assert_eq!(closes, vec![13.75, 15.0, 15.625]);
+        assert_eq!(usd[0].high(), 15.0);
+        assert_eq!(usd[0].volume(), 100.0);
+        assert_eq!(usd.options.currency, Some(Currency::USD.into()));
+
+        let back = usd
+            .convert_currency(Currency::USD, Currency::EUR, &eurusd)
+            .unwrap();
+        assert_eq!(back.data, prices.data);
+
+        assert!(matches!(
+            prices.convert_currency(Currency::EUR, Currency::JPY, &eurusd),
+            Err(Error::CurrencyConversion(_))
+        ));
+        let late = ChartHistoricalData::new(eurusd.options.clone(), vec![rate(86_400.0)]);
+        assert!(prices
+            .convert_currency(Currency::EUR, Currency::USD, &late)
+            .is_err());
+    }
+}
@@ -1,20 +1,123 @@
-use iso_currency::Currency;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use crate::{
-    chart::{ ChartOptions, StudyOptions },
-    models::{ Interval, MarketAdjustment, SessionType, pine_indicator::ScriptType },
+    chart::{ChartOptions, HistorySpec, StudyOptions, DEFAULT_BAR_COUNT, MAX_BARS_PER_REQUEST},
+    models::{
+        pine_indicator::ScriptType, CurrencyOverride, Interval, MarketAdjustment, SessionType,
+        Symbol,
+    },
+    Error, Result,
 };
+use tracing::warn;
 
 impl ChartOptions {
     pub fn new(symbol: &str, interval: Interval) -> Self {
         Self {
             symbol: symbol.to_string(),
             interval,
-            bar_count: 50_000,
+            bar_count: DEFAULT_BAR_COUNT,
             ..Default::default()
         }
     }
 
+    /// Options for a search result: `EXCHANGE:SYMBOL`, and the symbol's `currency_code` as the
+    /// chart currency when it has one.
+    pub fn from_symbol(symbol: &Symbol, interval: Interval) -> Self {
+        let ticker = if symbol.exchange.is_empty() {
+            symbol.symbol.clone()
+        } else {
+            format!("{}:{}", symbol.exchange, symbol.symbol)
+        };
+        let options = Self::new(&ticker, interval);
+        if symbol.currency_code.is_empty() {
+            options
+        } else {
+            options.currency(symbol.currency_code.as_str())
+        }
+    }
+
+    /// Options for `symbol` that set exactly the fields `spec` needs: `bar_count` for
+    /// [`HistorySpec::LastN`], `range` or `from`/`to` otherwise. Period based requests ask
+    /// for at most [`MAX_BARS_PER_REQUEST`] bars so they are served in a single request.
+    pub fn from_history(symbol: &str, interval: Interval, spec: HistorySpec) -> Self {
+        let options = Self {
+            symbol: symbol.to_string(),
+            interval,
+            ..Default::default()
+        };
+        match spec {
+            HistorySpec::LastN(n) => options.bar_count(n),
+            HistorySpec::Range(range) => options
+                .bar_count(MAX_BARS_PER_REQUEST)
+                .range(&range.to_string()),
+            HistorySpec::Between { from, to } => {
+                options.bar_count(MAX_BARS_PER_REQUEST).from(from).to(to)
+            }
+        }
+    }
+
+    /// Checks that the options request something: a non-zero `bar_count`, a `range`, or
+    /// both `from` and `to`. `ChartOptions::new` always passes, `ChartOptions::default` does not.
+    pub fn validate(&self) -> Result<()> {
+        let has_window = self.from.is_some() && self.to.is_some();
+        if self.bar_count == 0 && self.range.is_none() && !has_window {
+            return Err(Error::ChartRangeNotSpecified);
+        }
+        Ok(())
+    }
+
+    /// The range sent with `create_series`/`modify_series`. `range` takes precedence over
+    /// `from`/`to`; setting both logs a warning, or fails with
+    /// [`Error::ConflictingChartRange`] when the options are [`strict`](Self::strict).
+    pub(crate) fn series_range(&self) -> Result<String> {
+        match (&self.range, self.from, self.to) {
+            (Some(range), from, to) => {
+                if from.is_some() || to.is_some() {
+                    if self.strict {
+                        return Err(Error::ConflictingChartRange);
+                    }
+                    warn!(
+                        "both range {} and from/to are set for {}, from/to is ignored",
+                        range, self.symbol
+                    );
+                }
+                Ok(range.clone())
+            }
+            (None, Some(from), Some(to)) => Ok(format!("r,{}:{}", from, to)),
+            _ => Ok(String::default()),
+        }
+    }
+
+    /// Turns ambiguous options, such as `range` together with `from`/`to`, into errors
+    /// instead of warnings.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Drops the still-forming last bar from chart data before `on_chart_data` sees it, see
+    /// [`drop_unclosed_bar`](crate::chart::drop_unclosed_bar). Off by default. Live updates
+    /// only carrying the forming bar are then not delivered; the bar's last version is
+    /// delivered with the first update after it closed.
+    pub fn exclude_unclosed(mut self, exclude_unclosed: bool) -> Self {
+        self.exclude_unclosed = exclude_unclosed;
+        self
+    }
+
+    /// Makes `set_market` create a new series even when one with the same options is active.
+    pub fn force_new(mut self, force_new: bool) -> Self {
+        self.force_new = force_new;
+        self
+    }
+
+    /// Whether both options request the same series, ignoring `force_new`.
+    pub(crate) fn same_series(&self, other: &ChartOptions) -> bool {
+        ChartOptions {
+            force_new: other.force_new,
+            ..self.clone()
+        } == *other
+    }
+
     pub fn bar_count(mut self, bar_count: u64) -> Self {
         self.bar_count = bar_count;
         self
@@ -30,6 +133,19 @@ impl ChartOptions {
         self
     }
 
+    /// Enables replay starting `lookback` before now, e.g. 30 days for "replay the last month".
+    pub fn replay_lookback(mut self, lookback: Duration) -> Result<Self> {
+        if lookback.is_zero() {
+            return Err(Error::InvalidReplayLookback);
+        }
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        self.replay_mode = true;
+        self.replay_from = now.saturating_sub(lookback).as_secs() as i64;
+        Ok(self)
+    }
+
     pub fn replay_session_id(mut self, replay_session_id: &str) -> Self {
         self.replay_session = Some(replay_session_id.to_string());
         self
@@ -55,8 +171,9 @@ impl ChartOptions {
         self
     }
 
-    pub fn currency(mut self, currency: Currency) -> Self {
-        self.currency = Some(currency);
+    /// Accepts an ISO `Currency` or a code such as `"USDT"`.
+    pub fn currency(mut self, currency: impl Into<CurrencyOverride>) -> Self {
+        self.currency = Some(currency.into());
         self
     }
 
@@ -69,7 +186,7 @@ impl ChartOptions {
         mut self,
         script_id: &str,
         script_version: &str,
-        script_type: ScriptType
+        script_type: ScriptType,
     ) -> Self {
         self.study_config = Some(StudyOptions {
             script_id: script_id.to_string(),
@@ -79,3 +196,163 @@ impl ChartOptions {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Range;
+    use iso_currency::Currency;
+
+    #[test]
+    fn test_validate_requires_bar_count_or_range() {
+        let empty = ChartOptions {
+            symbol: "BINANCE:BTCUSDT".to_string(),
+            ..Default::default()
+        };
+        assert!(matches!(
+            empty.validate(),
+            Err(Error::ChartRangeNotSpecified)
+        ));
+        assert!(empty.clone().from(1).validate().is_err());
+
+        assert!(ChartOptions::new("BINANCE:BTCUSDT", Interval::Daily)
+            .validate()
+            .is_ok());
+        assert!(empty.clone().range("YTD").validate().is_ok());
+        assert!(empty.from(1).to(2).validate().is_ok());
+    }
+
+    #[test]
+    fn test_chart_options_serde_round_trip() {
+        let options = ChartOptions::new("NASDAQ:AAPL", Interval::FourHours)
+            .bar_count(1_000)
+            .range("YTD")
+            .from(1_700_000_000)
+            .to(1_710_000_000)
+            .replay_mode(true)
+            .replay_from(1_705_000_000)
+            .replay_session_id("rs_test")
+            .adjustment(MarketAdjustment::Dividends)
+            .currency(Currency::EUR)
+            .session_type(SessionType::Extended)
+            .study_config("STD;RSI", "31.0", ScriptType::IntervalScript);
+
+        let json = serde_json::to_string(&options).unwrap();
+        let restored: ChartOptions = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, options);
+    }
+
+    #[test]
+    fn test_replay_lookback() {
+        let lookback = Duration::from_secs(30 * 24 * 60 * 60);
+        let options = ChartOptions::new("BINANCE:BTCUSDT", Interval::OneHour)
+            .replay_lookback(lookback)
+            .unwrap();
+        let expected = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64
+            - lookback.as_secs() as i64;
+
+        assert!(options.replay_mode);
+        assert!((options.replay_from - expected).abs() <= 2);
+        assert!(matches!(
+            ChartOptions::default().replay_lookback(Duration::ZERO),
+            Err(Error::InvalidReplayLookback)
+        ));
+    }
+
+    #[test]
+    fn test_series_range_precedence() {
+        let options = ChartOptions::new("BINANCE:BTCUSDT", Interval::Daily);
+        assert_eq!(options.series_range().unwrap(), "");
+        assert_eq!(
+            options.clone().from(1).to(2).series_range().unwrap(),
+            "r,1:2"
+        );
+
+        let both = options.range("YTD").from(1).to(2);
+        assert_eq!(both.series_range().unwrap(), "YTD");
+        assert!(matches!(
+            both.strict(true).series_range(),
+            Err(Error::ConflictingChartRange)
+        ));
+    }
+
+    #[test]
+    fn test_default_bar_count() {
+        let options = ChartOptions::new("BINANCE:BTCUSDT", Interval::Daily);
+        assert_eq!(options.bar_count, DEFAULT_BAR_COUNT);
+        assert_eq!(options.bar_count(500).bar_count, 500);
+    }
+
+    #[test]
+    fn test_from_history_last_n() {
+        let options =
+            ChartOptions::from_history("NASDAQ:AAPL", Interval::OneHour, HistorySpec::LastN(300));
+        assert_eq!(options.bar_count, 300);
+        assert_eq!(options.series_range().unwrap(), "");
+        assert!(options.validate().is_ok());
+    }
+
+    #[test]
+    fn test_from_history_range() {
+        let options = ChartOptions::from_history(
+            "NASDAQ:AAPL",
+            Interval::Daily,
+            HistorySpec::Range(Range::YearToDate),
+        );
+        assert_eq!(options.bar_count, MAX_BARS_PER_REQUEST);
+        assert_eq!(options.series_range().unwrap(), "YTD");
+        assert_eq!((options.from, options.to), (None, None));
+    }
+
+    #[test]
+    fn test_from_history_between() {
+        let options = ChartOptions::from_history(
+            "NASDAQ:AAPL",
+            Interval::Daily,
+            HistorySpec::Between {
+                from: 1_626_220_800,
+                to: 1_628_640_000,
+            },
+        );
+        assert_eq!(options.series_range().unwrap(), "r,1626220800:1628640000");
+        assert_eq!(options.range, None);
+        assert!(options.strict(true).series_range().is_ok());
+    }
+
+    #[test]
+    fn test_from_symbol() {
+        let symbol = Symbol {
+            symbol: "AAPL".to_string(),
+            exchange: "NASDAQ".to_string(),
+            currency_code: "USD".to_string(),
+            ..Default::default()
+        };
+        let options = ChartOptions::from_symbol(&symbol, Interval::OneHour);
+        assert_eq!(options.symbol, "NASDAQ:AAPL");
+        assert_eq!(options.interval, Interval::OneHour);
+        assert_eq!(options.currency, Some(CurrencyOverride::Iso(Currency::USD)));
+        assert_eq!(options.bar_count, DEFAULT_BAR_COUNT);
+
+        let crypto = Symbol {
+            symbol: "BTCUSDT".to_string(),
+            exchange: "BINANCE".to_string(),
+            currency_code: "USDT".to_string(),
+            ..Default::default()
+        };
+        let options = ChartOptions::from_symbol(&crypto, Interval::Daily);
+        assert_eq!(
+            options.currency,
+            Some(CurrencyOverride::Code("USDT".to_string()))
+        );
+
+        let bare = Symbol {
+            symbol: "SPX".to_string(),
+            ..Default::default()
+        };
+        let options = ChartOptions::from_symbol(&bare, Interval::Daily);
+        assert_eq!((options.symbol.as_str(), options.currency), ("SPX", None));
+    }
+}
@@ -0,0 +1,62 @@
+use std::io::Write;
+
+use ::parquet::{
+    arrow::{arrow_reader::ParquetRecordBatchReaderBuilder, ArrowWriter},
+    file::reader::ChunkReader,
+};
+
+use crate::{chart::models::ChartHistoricalData, Result};
+
+impl ChartHistoricalData {
+    /// Writes [`ChartHistoricalData::to_arrow`] as a Parquet file.
+    pub fn write_parquet<W: Write + Send>(&self, writer: W) -> Result<()> {
+        let batch = self.to_arrow()?;
+        let mut writer = ArrowWriter::try_new(writer, batch.schema(), None)?;
+        writer.write(&batch)?;
+        writer.close()?;
+        Ok(())
+    }
+
+    /// Reads bars written by [`write_parquet`](Self::write_parquet), e.g. from a `File`.
+    /// The result has default `ChartOptions`.
+    pub fn read_parquet<R: ChunkReader + 'static>(reader: R) -> Result<Self> {
+        let mut data = ChartHistoricalData::default();
+        for batch in ParquetRecordBatchReaderBuilder::try_new(reader)?.build()? {
+            let mut bars = ChartHistoricalData::from_arrow(&batch?)?.data;
+            for bar in bars.iter_mut() {
+                bar.index += data.data.len() as i64;
+            }
+            data.data.extend(bars);
+        }
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chart::{models::DataPoint, ChartOptions};
+
+    #[test]
+    fn test_parquet_round_trip() {
+        let bar = |index: i64, ts: f64, close: f64| DataPoint {
+            index,
+            value: vec![ts, close - 1.0, close + 2.0, close - 3.0, close, 42.5],
+        };
+        let data = ChartHistoricalData::new(
+            ChartOptions::default(),
+            vec![bar(0, 0.0, 10.1), bar(1, 60.0, 11.2), bar(2, 120.0, 9.3)],
+        );
+
+        let path = std::env::temp_dir().join(format!(
+            "tradingview-parquet-{}.parquet",
+            std::process::id()
+        ));
+        data.write_parquet(std::fs::File::create(&path).unwrap())
+            .unwrap();
+        let read = ChartHistoricalData::read_parquet(std::fs::File::open(&path).unwrap());
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(read.unwrap().data, data.data);
+    }
+}
@@ -1,4 +1,114 @@
+use crate::{chart::models::DataPoint, models::Interval};
+
+/// Sorts `points` ascending by timestamp and removes bars sharing a timestamp.
+///
+/// When several bars share a timestamp the one that appears last in `points` wins,
+/// so appending a fresher update for an existing bar and deduping keeps the update.
+pub fn dedup_bars(points: &mut Vec<DataPoint>) {
+    points.reverse();
+    // stable sort keeps the reversed (latest first) order among equal timestamps
+    points.sort_by_key(|p| p.timestamp());
+    points.dedup_by_key(|p| p.timestamp());
+}
+
+/// The interval the bars were actually sampled at, taken from the smallest gap between
+/// consecutive timestamps and matched to the nearest [`Interval`]. Needs at least two
+/// distinct timestamps; `points` need not be sorted.
+pub fn infer_interval(points: &[DataPoint]) -> Option<Interval> {
+    let mut timestamps: Vec<i64> = points.iter().map(DataPoint::timestamp).collect();
+    timestamps.sort_unstable();
+    let spacing = timestamps
+        .windows(2)
+        .map(|w| w[1] - w[0])
+        .filter(|gap| *gap > 0)
+        .min()? as f64;
+    Interval::all().into_iter().min_by(|a, b| {
+        let distance = |i: &Interval| (spacing / i.seconds() as f64).ln().abs();
+        distance(a).total_cmp(&distance(b))
+    })
+}
+
+/// Fewest bars [`detect_downgrade`] draws a conclusion from.
+pub const MIN_BARS_FOR_DOWNGRADE: usize = 10;
+
+/// The interval `points` were sampled at, if it is coarser than `requested`.
+///
+/// TradingView does not echo the resolution it serves, so this is a guess from the bar
+/// spacing, see [`infer_interval`]. It needs at least [`MIN_BARS_FOR_DOWNGRADE`] bars,
+/// which rules out single bar `du` updates. It can still report a false downgrade for a
+/// sparse series whose bars are all further apart than the interval, e.g. a symbol that
+/// trades only a few times an hour requested at one minute.
+pub fn detect_downgrade(points: &[DataPoint], requested: Interval) -> Option<Interval> {
+    if points.len() < MIN_BARS_FOR_DOWNGRADE {
+        return None;
+    }
+    infer_interval(points).filter(|actual| *actual > requested)
+}
+
+/// Removes the last bar of `points` (sorted by time) if it is still forming at `now`, i.e.
+/// its [`next_boundary`](Interval::next_boundary) has not passed, and returns it.
+pub fn drop_unclosed_bar(
+    points: &mut Vec<DataPoint>,
+    interval: Interval,
+    now: i64,
+) -> Option<DataPoint> {
+    let last = points.last()?;
+    if interval.next_boundary(last.timestamp()) > now {
+        points.pop()
+    } else {
+        None
+    }
+}
+
 // TODO: Implement this module
 pub fn _graphics_parser() {
     unimplemented!()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bar(timestamp: f64, close: f64) -> DataPoint {
+        DataPoint {
+            index: 0,
+            value: vec![timestamp, close, close, close, close, 0.0],
+        }
+    }
+
+    #[test]
+    fn test_dedup_bars_latest_wins() {
+        let mut points = vec![
+            bar(3.0, 30.0),
+            bar(1.0, 10.0),
+            bar(2.0, 20.0),
+            bar(1.0, 11.0),
+            bar(3.0, 31.0),
+            bar(2.0, 21.0),
+            bar(3.0, 32.0),
+        ];
+        dedup_bars(&mut points);
+
+        let result: Vec<(i64, f64)> = points.iter().map(|p| (p.timestamp(), p.value[4])).collect();
+        assert_eq!(result, vec![(1, 11.0), (2, 21.0), (3, 32.0)]);
+    }
+
+    #[test]
+    fn test_drop_unclosed_bar() {
+        let now = 1_700_000_000;
+        let day = 86_400;
+        let mut points = vec![
+            bar((now - 2 * day) as f64, 1.0),
+            bar((now - day - 10) as f64, 2.0),
+            bar((now - 10) as f64, 3.0),
+        ];
+        let dropped = drop_unclosed_bar(&mut points, Interval::Daily, now);
+        assert_eq!(dropped.map(|p| p.timestamp()), Some(now - 10));
+        assert_eq!(points.len(), 2);
+
+        // the remaining last bar closed 10s ago
+        assert!(drop_unclosed_bar(&mut points, Interval::Daily, now).is_none());
+        assert_eq!(points.len(), 2);
+        assert!(drop_unclosed_bar(&mut Vec::new(), Interval::Daily, now).is_none());
+    }
+}
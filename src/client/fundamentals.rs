@@ -0,0 +1,121 @@
+use crate::{
+    client::misc::search_one_symbol, utils::get, DetailedSymbol, FinancialPeriod, Fundamentals,
+    Result, Symbol,
+};
+use serde_json::Value;
+
+static SCANNER_SYMBOL_URL: &str = "https://scanner.tradingview.com/symbol";
+
+/// Retrieves the financial statement figures of a symbol for the given period.
+///
+/// # Arguments
+///
+/// * `symbol` - The full symbol id, e.g. `NASDAQ:AAPL`.
+/// * `period` - The reporting period, e.g. `FinancialPeriod::FiscalQuarter` for the latest quarter.
+///
+/// # Returns
+///
+/// A `Result` containing the `Fundamentals` of the latest report for that period, or an error if the request fails.
+#[tracing::instrument]
+pub async fn get_fundamentals(symbol: &str, period: FinancialPeriod) -> Result<Fundamentals> {
+    let fields = Fundamentals::scanner_fields(&period).join(",");
+    let data: Value = get(
+        None,
+        SCANNER_SYMBOL_URL,
+        &[("symbol", symbol), ("fields", &fields), ("no_404", "true")],
+    )
+    .await?
+    .json()
+    .await?;
+    Ok(Fundamentals::from_scanner(symbol, period, &data))
+}
+
+/// Resolves `symbol` on `exchange` like [`search_one_symbol`] and adds its sector, industry,
+/// market cap and currency from the scanner. See
+/// [`SymbolCache::get_symbol_detailed`](crate::client::symbol_cache::SymbolCache::get_symbol_detailed)
+/// for a cached lookup.
+#[tracing::instrument]
+pub async fn get_symbol_detailed(symbol: &str, exchange: &str) -> Result<DetailedSymbol> {
+    let found = search_one_symbol(symbol, exchange).await?;
+    symbol_details(found).await
+}
+
+/// Adds the scanner fields of an already resolved symbol.
+pub(crate) async fn symbol_details(found: Symbol) -> Result<DetailedSymbol> {
+    let id = format!("{}:{}", found.exchange, found.symbol);
+    let fields = DetailedSymbol::FIELDS.join(",");
+    let data: Value = get(
+        None,
+        SCANNER_SYMBOL_URL,
+        &[("symbol", &id), ("fields", &fields), ("no_404", "true")],
+    )
+    .await?
+    .json()
+    .await?;
+    Ok(DetailedSymbol::from_scanner(found, &data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fundamentals_from_scanner() {
+        let current_dir = std::env::current_dir().unwrap().display().to_string();
+        let raw = std::fs::read_to_string(format!(
+            "{}/tests/data/fundamentals_response.json",
+            current_dir
+        ))
+        .unwrap();
+        let data: Value = serde_json::from_str(&raw).unwrap();
+
+        let fq = Fundamentals::from_scanner("NASDAQ:AAPL", FinancialPeriod::FiscalQuarter, &data);
+        assert_eq!(fq.period, FinancialPeriod::FiscalQuarter);
+        assert_eq!(fq.total_revenue, Some(85_777_000_000.0));
+        assert_eq!(fq.earnings_per_share_diluted, Some(1.4));
+        assert_eq!(fq.total_assets, Some(331_612_000_000.0));
+        assert_eq!(fq.free_cash_flow, None);
+
+        let ttm =
+            Fundamentals::from_scanner("NASDAQ:AAPL", FinancialPeriod::TrailingTwelveMonths, &data);
+        assert_eq!(ttm.net_income, Some(101_956_000_000.0));
+        assert_eq!(ttm.total_assets, None);
+    }
+
+    #[test]
+    fn test_detailed_symbol_from_scanner() {
+        let current_dir = std::env::current_dir().unwrap().display().to_string();
+        let raw = std::fs::read_to_string(format!(
+            "{}/tests/data/symbol_detail_response.json",
+            current_dir
+        ))
+        .unwrap();
+        let data: Value = serde_json::from_str(&raw).unwrap();
+        let symbol = crate::Symbol {
+            symbol: "AAPL".to_string(),
+            exchange: "NASDAQ".to_string(),
+            ..Default::default()
+        };
+
+        let detailed = DetailedSymbol::from_scanner(symbol.clone(), &data);
+        assert_eq!(detailed.symbol, symbol);
+        assert_eq!(detailed.sector.as_deref(), Some("Electronic Technology"));
+        assert_eq!(
+            detailed.industry.as_deref(),
+            Some("Telecommunications Equipment")
+        );
+        assert_eq!(detailed.market_cap, Some(3_431_000_000_000.0));
+        assert_eq!(detailed.currency.as_deref(), Some("USD"));
+
+        let bare = DetailedSymbol::from_scanner(symbol, &serde_json::json!({ "sector": "" }));
+        assert_eq!(bare.sector, None);
+        assert_eq!(bare.market_cap, None);
+    }
+
+    #[test]
+    fn test_fundamentals_scanner_fields() {
+        let fields = Fundamentals::scanner_fields(&FinancialPeriod::FiscalYear);
+        assert_eq!(fields.len(), Fundamentals::FIELDS.len());
+        assert_eq!(fields[0], "total_revenue_fy");
+    }
+}
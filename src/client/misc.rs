@@ -1,7 +1,8 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use crate::{
     error::Error,
+    models::dedup_symbols,
     pine_indicator::{self, BuiltinIndicators, PineInfo, PineMetadata, PineSearchResult},
     utils::build_request,
     ChartDrawing, CryptoCentralization, EconomicCategory, EconomicSource, FuturesProductType,
@@ -14,6 +15,9 @@ use tracing::debug;
 
 static SEARCH_BASE_URL: &str = "https://symbol-search.tradingview.com/symbol_search/v3/";
 
+/// Number of symbols the search endpoint returns per page unless a `limit` is given.
+pub const SEARCH_PAGE_SIZE: u64 = 50;
+
 /// Sends an HTTP GET request to the specified URL using the provided client and returns the response.
 ///
 /// # Arguments
@@ -37,48 +41,80 @@ async fn get(client: Option<&UserCookies>, url: &str) -> Result<Response> {
     Ok(build_request(None)?.get(url).send().await?)
 }
 
+/// Resolves the best match for `search` on `exchange`.
+///
+/// Every call sends a request; see
+/// [`SymbolCache`](crate::client::symbol_cache::SymbolCache) for a cached lookup.
 pub async fn search_one_symbol(search: &str, exchange: &str) -> Result<Symbol> {
-    let search_data = advanced_search_symbol(
-        search,
-        exchange,
-        &MarketType::All,
-        0,
-        None,
-        None,
-        None,
-        None,
-        None,
-        None,
-        None,
-    )
-    .await?;
-    let symbol = match search_data.symbols.first() {
-        Some(symbol) => symbol,
-        None => {
-            return Err(Error::Generic("No symbol found".to_string()));
-        }
-    };
-    Ok(symbol.to_owned())
+    let search_data = SymbolSearch::new(search).exchange(exchange).send().await?;
+    match search_data.symbols.into_iter().next() {
+        Some(symbol) => Ok(symbol),
+        None => Err(Error::Generic("No symbol found".to_string())),
+    }
 }
 
 pub async fn search_symbols(search: &str, exchange: &str) -> Result<Vec<Symbol>> {
-    let search_data = advanced_search_symbol(
-        search,
-        exchange,
-        &MarketType::All,
-        0,
-        None,
-        None,
-        None,
-        None,
-        None,
-        None,
-        None,
-    )
-    .await?;
+    let search_data = SymbolSearch::new(search).exchange(exchange).send().await?;
     Ok(search_data.symbols)
 }
 
+/// Searches for symbols and ranks the results with [`score_symbol`], best match first.
+///
+/// Useful for typeahead UIs where an exact ticker should beat a description hit.
+pub async fn search_symbols_ranked(search: &str, exchange: &str) -> Result<Vec<(Symbol, f32)>> {
+    search_symbols_ranked_by(search, exchange, score_symbol).await
+}
+
+/// Same as [`search_symbols_ranked`] but with a caller supplied scoring function.
+pub async fn search_symbols_ranked_by(
+    search: &str,
+    exchange: &str,
+    scorer: impl Fn(&str, &Symbol) -> f32,
+) -> Result<Vec<(Symbol, f32)>> {
+    let symbols = search_symbols(search, exchange).await?;
+    Ok(rank_symbols(search, symbols, scorer))
+}
+
+/// Default relevance score of `symbol` for `query`, case-insensitive.
+///
+/// * `1.0` - the ticker equals the query
+/// * `0.5..1.0` - the ticker starts with the query, shorter tickers score higher
+/// * `0.25` - the description contains the query
+/// * `0.0` - no match
+pub fn score_symbol(query: &str, symbol: &Symbol) -> f32 {
+    let query = query.trim().to_uppercase();
+    if query.is_empty() {
+        return 0.0;
+    }
+    let ticker = symbol.symbol.to_uppercase();
+    if ticker == query {
+        1.0
+    } else if ticker.starts_with(&query) {
+        0.5 + 0.25 * (query.len() as f32 / ticker.len() as f32)
+    } else if symbol.description.to_uppercase().contains(&query) {
+        0.25
+    } else {
+        0.0
+    }
+}
+
+/// Scores every symbol and sorts them by descending score. Ties keep the server order.
+pub fn rank_symbols(
+    query: &str,
+    symbols: Vec<Symbol>,
+    scorer: impl Fn(&str, &Symbol) -> f32,
+) -> Vec<(Symbol, f32)> {
+    let mut ranked: Vec<(Symbol, f32)> = symbols
+        .into_iter()
+        .map(|symbol| {
+            let score = scorer(query, &symbol);
+            (symbol, score)
+        })
+        .collect();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+    ranked
+}
+
 /// Searches for a symbol using the specified search parameters.
 ///
 /// # Arguments
@@ -92,7 +128,9 @@ pub async fn search_symbols(search: &str, exchange: &str) -> Result<Vec<Symbol>>
 ///
 /// # Returns
 ///
-/// A `Result` containing a `SymbolSearchResponse` struct representing the search results, or an error if the search failed.
+/// A `Result` containing a `SymbolSearchResponse` struct with the page starting at `start`;
+/// `remaining` is the number of matches after this page. Use [`SymbolSearch`] to also set
+/// the page size.
 #[allow(clippy::too_many_arguments)]
 pub async fn advanced_search_symbol(
     search: &str,
@@ -107,63 +145,207 @@ pub async fn advanced_search_symbol(
     economic_source: Option<&EconomicSource>,  // For Economy Only
     economic_category: Option<&EconomicCategory>, // For Economy Only
 ) -> Result<SymbolSearchResponse> {
-    let mut params: Vec<(String, String)> = Vec::new();
-    let domain = domain.unwrap_or("production");
-    params.push(("text".to_string(), search.to_string()));
-    params.push(("exchange".to_string(), exchange.to_string()));
-    params.push(("search_type".to_string(), market_type.to_string()));
-    params.push(("domain".to_string(), domain.to_string()));
-    if let Some(country) = country {
-        params.push(("country".to_string(), country.to_string()));
-        params.push(("sort_by_country".to_string(), country.to_string()));
-    }
-    match market_type {
-        MarketType::Futures => {
-            if let Some(futures_type) = futures_type {
-                params.push(("product".to_string(), futures_type.to_string()));
-            }
+    SymbolSearch {
+        search: search.to_string(),
+        exchange: exchange.to_string(),
+        market_type: *market_type,
+        start,
+        limit: None,
+        country: country.map(str::to_string),
+        domain: domain.map(str::to_string),
+        futures_type: futures_type.copied(),
+        stock_sector: stock_sector.copied(),
+        crypto_centralization: crypto_centralization.copied(),
+        economic_source: economic_source.copied(),
+        economic_category: economic_category.copied(),
+        timeout: None,
+    }
+    .send()
+    .await
+}
+
+/// A symbol search request, the builder form of [`advanced_search_symbol`].
+///
+/// ```no_run
+/// # async fn run() -> tradingview::Result<()> {
+/// use tradingview::{MarketType, SymbolSearch};
+///
+/// let page = SymbolSearch::new("BTC")
+///     .market_type(MarketType::All)
+///     .start(50)
+///     .limit(50)
+///     .send()
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SymbolSearch {
+    pub search: String,
+    pub exchange: String,
+    pub market_type: MarketType,
+    /// Index of the first match to return.
+    pub start: u64,
+    /// Matches per page, [`SEARCH_PAGE_SIZE`] if `None`.
+    pub limit: Option<u64>,
+    pub country: Option<String>,
+    /// Defaults to "production".
+    pub domain: Option<String>,
+    /// Only used with [`MarketType::Futures`].
+    pub futures_type: Option<FuturesProductType>,
+    /// Only used with [`MarketType::Stocks`].
+    pub stock_sector: Option<StockSector>,
+    /// Only used with [`MarketType::Crypto`].
+    pub crypto_centralization: Option<CryptoCentralization>,
+    /// Only used with [`MarketType::Economy`].
+    pub economic_source: Option<EconomicSource>,
+    /// Only used with [`MarketType::Economy`].
+    pub economic_category: Option<EconomicCategory>,
+    /// [`DEFAULT_REQUEST_TIMEOUT`](crate::DEFAULT_REQUEST_TIMEOUT) if `None`.
+    pub timeout: Option<Duration>,
+}
+
+impl SymbolSearch {
+    pub fn new(search: &str) -> Self {
+        SymbolSearch {
+            search: search.to_string(),
+            ..Default::default()
         }
-        MarketType::Stocks(_) => {
-            if let Some(stock_sector) = stock_sector {
-                params.push(("sector".to_string(), stock_sector.to_string()));
-            }
+    }
+
+    pub fn exchange(mut self, exchange: &str) -> Self {
+        self.exchange = exchange.to_string();
+        self
+    }
+
+    pub fn market_type(mut self, market_type: MarketType) -> Self {
+        self.market_type = market_type;
+        self
+    }
+
+    pub fn start(mut self, start: u64) -> Self {
+        self.start = start;
+        self
+    }
+
+    pub fn limit(mut self, limit: u64) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn country(mut self, country: &str) -> Self {
+        self.country = Some(country.to_string());
+        self
+    }
+
+    pub fn domain(mut self, domain: &str) -> Self {
+        self.domain = Some(domain.to_string());
+        self
+    }
+
+    pub fn futures_type(mut self, futures_type: FuturesProductType) -> Self {
+        self.futures_type = Some(futures_type);
+        self
+    }
+
+    pub fn stock_sector(mut self, stock_sector: StockSector) -> Self {
+        self.stock_sector = Some(stock_sector);
+        self
+    }
+
+    pub fn crypto_centralization(mut self, crypto_centralization: CryptoCentralization) -> Self {
+        self.crypto_centralization = Some(crypto_centralization);
+        self
+    }
+
+    pub fn economic_source(mut self, economic_source: EconomicSource) -> Self {
+        self.economic_source = Some(economic_source);
+        self
+    }
+
+    pub fn economic_category(mut self, economic_category: EconomicCategory) -> Self {
+        self.economic_category = Some(economic_category);
+        self
+    }
+
+    /// Fails the request with `Error::Timeout` after `timeout`.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Requests the page; `remaining` in the response is the number of matches after it,
+    /// so the next page starts at `start + symbols.len()`.
+    pub async fn send(&self) -> Result<SymbolSearchResponse> {
+        self.fetch(&build_request(None)?, SEARCH_BASE_URL).await
+    }
+
+    async fn fetch(
+        &self,
+        client: &reqwest::Client,
+        base_url: &str,
+    ) -> Result<SymbolSearchResponse> {
+        let url = format!("{base_url}?{}", self.query());
+        let mut request = client.get(url);
+        if let Some(timeout) = self.timeout {
+            request = request.timeout(timeout);
         }
-        MarketType::Crypto(_) => {
-            if let Some(crypto_centralization) = crypto_centralization {
-                params.push((
-                    "centralization".to_string(),
-                    crypto_centralization.to_string(),
-                ));
-            }
+        Ok(request.send().await?.json().await?)
+    }
+
+    pub(crate) fn query(&self) -> String {
+        let mut params: Vec<(&str, String)> = vec![
+            ("text", self.search.clone()),
+            ("exchange", self.exchange.clone()),
+            ("search_type", self.market_type.to_string()),
+            (
+                "domain",
+                self.domain.clone().unwrap_or("production".to_string()),
+            ),
+        ];
+        if let Some(country) = &self.country {
+            params.push(("country", country.clone()));
+            params.push(("sort_by_country", country.clone()));
         }
-        MarketType::Economy => {
-            if let Some(economic_source) = economic_source {
-                params.push(("source_id".to_string(), economic_source.to_string()));
+        match self.market_type {
+            MarketType::Futures => {
+                if let Some(futures_type) = self.futures_type {
+                    params.push(("product", futures_type.to_string()));
+                }
+            }
+            MarketType::Stocks(_) => {
+                if let Some(stock_sector) = self.stock_sector {
+                    params.push(("sector", stock_sector.to_string()));
+                }
             }
-            if let Some(economic_category) = economic_category {
-                params.push((
-                    "economic_category".to_string(),
-                    economic_category.to_string(),
-                ));
+            MarketType::Crypto(_) => {
+                if let Some(crypto_centralization) = self.crypto_centralization {
+                    params.push(("centralization", crypto_centralization.to_string()));
+                }
+            }
+            MarketType::Economy => {
+                if let Some(economic_source) = self.economic_source {
+                    params.push(("source_id", economic_source.to_string()));
+                }
+                if let Some(economic_category) = self.economic_category {
+                    params.push(("economic_category", economic_category.to_string()));
+                }
             }
+            _ => {}
+        };
+        params.push(("hl", "0".to_string()));
+        params.push(("lang", "en".to_string()));
+        params.push(("start", self.start.to_string()));
+        if let Some(limit) = self.limit {
+            params.push(("limit", limit.to_string()));
         }
-        _ => {}
-    };
 
-    let params_str = params
-        .iter()
-        .map(|(k, v)| format!("{}={}", k, v))
-        .collect::<Vec<String>>()
-        .join("&");
-
-    let search_data: SymbolSearchResponse = get(
-        None,
-        &format!("{SEARCH_BASE_URL}?{params_str}&hl=0&lang=en&start={start}"),
-    )
-    .await?
-    .json()
-    .await?;
-    Ok(search_data)
+        params
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect::<Vec<String>>()
+            .join("&")
+    }
 }
 
 /// Lists symbols based on the specified search parameters.
@@ -172,7 +354,7 @@ pub async fn advanced_search_symbol(
 ///
 /// * `exchange` - An optional string representing the exchange to search in.
 /// * `market_type` - An optional `SymbolMarketType` enum representing the type of market to search in.
-/// * `country` - An optional string representing the country to search in.
+/// * `country` - An optional alpha-2 country code to search in, e.g. `Country::Vietnam.to_string()`.
 /// * `domain` - An optional string representing the domain to search in.
 ///
 /// # Returns
@@ -185,25 +367,13 @@ pub async fn list_symbols(
     country: Option<String>,
     domain: Option<String>,
 ) -> Result<Vec<Symbol>> {
-    let market_type: Arc<MarketType> = Arc::new(market_type.unwrap_or_default());
-    let exchange: Arc<String> = Arc::new(exchange.unwrap_or("".to_string()));
-    let country = Arc::new(country.unwrap_or("".to_string()));
-    let domain = Arc::new(domain.unwrap_or("production".to_string()));
-
-    let search_symbol_reps = advanced_search_symbol(
-        "",
-        &exchange,
-        &market_type,
-        0,
-        Some(&country),
-        Some(&domain),
-        None,
-        None,
-        None,
-        None,
-        None,
-    )
-    .await?;
+    let search = SymbolSearch::new("")
+        .exchange(&exchange.unwrap_or_default())
+        .market_type(market_type.unwrap_or_default())
+        .country(&country.unwrap_or_default())
+        .domain(&domain.unwrap_or("production".to_string()));
+
+    let search_symbol_reps = search.send().await?;
     let remaining = search_symbol_reps.remaining;
     let mut symbols = search_symbol_reps.symbols;
 
@@ -212,30 +382,13 @@ pub async fn list_symbols(
 
     let mut tasks = Vec::new();
 
-    for i in (50..remaining).step_by(50) {
-        let market_type = Arc::clone(&market_type);
-        let exchange = Arc::clone(&exchange);
-        let country = Arc::clone(&country);
-        let domain = Arc::clone(&domain);
+    for i in (SEARCH_PAGE_SIZE..remaining).step_by(SEARCH_PAGE_SIZE as usize) {
+        let search = search.clone().start(i);
         let semaphore = Arc::clone(&semaphore);
 
         let task = tokio::spawn(async move {
             let _permit = semaphore.acquire().await.unwrap();
-            advanced_search_symbol(
-                "",
-                &exchange,
-                &market_type,
-                i,
-                Some(&country),
-                Some(&domain),
-                None,
-                None,
-                None,
-                None,
-                None,
-            )
-            .await
-            .map(|resp| resp.symbols)
+            search.send().await.map(|resp| resp.symbols)
         });
 
         tasks.push(task);
@@ -244,6 +397,7 @@ pub async fn list_symbols(
     for handler in tasks {
         symbols.extend(handler.await??);
     }
+    dedup_symbols(&mut symbols);
 
     Ok(symbols)
 }
@@ -478,3 +632,143 @@ pub async fn get_indicator_metadata(
         "Failed to get indicator metadata".to_string(),
     ))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn symbol(ticker: &str, description: &str) -> Symbol {
+        Symbol {
+            symbol: ticker.to_string(),
+            description: description.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_rank_symbols_exact_match_first() {
+        let symbols = vec![
+            symbol("AAPLX", "Fund tracking AAPL"),
+            symbol("MSFT", "Microsoft holds no aapl"),
+            symbol("AAPL", "Apple Inc."),
+        ];
+        let ranked = rank_symbols("aapl", symbols, score_symbol);
+
+        assert_eq!(ranked[0].0.symbol, "AAPL");
+        assert_eq!(ranked[0].1, 1.0);
+        assert_eq!(ranked[1].0.symbol, "AAPLX");
+        assert_eq!(ranked[2].0.symbol, "MSFT");
+        assert!(ranked[1].1 > ranked[2].1);
+    }
+
+    #[test]
+    fn test_rank_symbols_custom_scorer() {
+        let symbols = vec![symbol("A", ""), symbol("BBB", "")];
+        let ranked = rank_symbols("", symbols, |_, s| s.symbol.len() as f32);
+        assert_eq!(ranked[0].0.symbol, "BBB");
+    }
+
+    #[test]
+    fn test_symbol_search_query() {
+        let query = SymbolSearch::new("BTC").exchange("BINANCE").query();
+        assert_eq!(
+            query,
+            "text=BTC&exchange=BINANCE&search_type=undefined&domain=production&hl=0&lang=en&start=0"
+        );
+        let query = SymbolSearch::new("BTC").start(50).limit(50).query();
+        assert!(query.ends_with("&start=50&limit=50"));
+    }
+
+    #[tokio::test]
+    async fn test_symbol_search_timeout() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            // accept and never answer, not even the TLS handshake
+            let (stream, _) = listener.accept().await.unwrap();
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            drop(stream);
+        });
+
+        let result = SymbolSearch::new("BTC")
+            .timeout(Duration::from_millis(200))
+            .fetch(
+                &build_request(None).unwrap(),
+                &format!("https://{addr}/symbol_search/v3/"),
+            )
+            .await;
+        server.abort();
+
+        assert!(matches!(result, Err(Error::Timeout(_))));
+    }
+
+    #[tokio::test]
+    async fn test_symbol_search_sends_start_and_limit() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let current_dir = std::env::current_dir().unwrap().display().to_string();
+        let body = std::fs::read(format!(
+            "{}/tests/data/symbol_search_page_2.json",
+            current_dir
+        ))
+        .unwrap();
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 1024];
+            while !request.ends_with(b"\r\n\r\n") {
+                let n = stream.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            let head = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n",
+                body.len()
+            );
+            stream.write_all(head.as_bytes()).await.unwrap();
+            stream.write_all(&body).await.unwrap();
+            let request = String::from_utf8(request).unwrap();
+            request.lines().next().unwrap().to_string()
+        });
+
+        let client = reqwest::Client::new();
+        let page = SymbolSearch::new("BTC")
+            .start(50)
+            .limit(50)
+            .fetch(&client, &format!("http://{addr}/symbol_search/v3/"))
+            .await
+            .unwrap();
+
+        let request_line = server.await.unwrap();
+        assert!(request_line.starts_with("GET /symbol_search/v3/?text=BTC&"));
+        assert!(request_line.contains("&start=50&limit=50 "));
+        assert!(!page.symbols.is_empty());
+    }
+
+    #[test]
+    fn test_search_pages_do_not_overlap() {
+        let page = |n: u8| -> SymbolSearchResponse {
+            let current_dir = std::env::current_dir().unwrap().display().to_string();
+            let data = std::fs::read_to_string(format!(
+                "{}/tests/data/symbol_search_page_{}.json",
+                current_dir, n
+            ))
+            .unwrap();
+            serde_json::from_str(&data).unwrap()
+        };
+        // hand-written in the shape of consecutive `limit=5` pages of the v3 endpoint
+        let (first, second) = (page(1), page(2));
+
+        assert_eq!(
+            first.remaining - second.remaining,
+            second.symbols.len() as u64
+        );
+        assert!(!second.symbols.is_empty());
+        assert!(second
+            .symbols
+            .iter()
+            .all(|s| !first.symbols.iter().any(|f| f.id() == s.id())));
+    }
+}
@@ -1,4 +1,7 @@
 pub mod fin_calendar;
+pub mod fundamentals;
 pub mod misc;
 pub mod news;
+pub mod screener;
+pub mod symbol_cache;
 pub mod websocket;
@@ -1,6 +1,6 @@
 use crate::{
-    utils::get, MarketType, News, NewsArea, NewsContent, NewsHeadlines, NewsSection, Result,
-    UserCookies,
+    utils::get, MarketType, News, NewsArea, NewsContent, NewsFilter, NewsHeadlines, NewsPage,
+    NewsSection, Result, UserCookies,
 };
 
 static BASE_NEWS_URL: &str = "https://news-headlines.tradingview.com/v2";
@@ -51,9 +51,22 @@ pub async fn list_news(
     area: Option<&NewsArea>,
     section: Option<&NewsSection>,
 ) -> Result<NewsHeadlines> {
-    let category = get_news_category(category);
+    let queries = headlines_queries(category, area, section);
+    let res = get(client, &format!("{BASE_NEWS_URL}/headlines"), &queries)
+        .await?
+        .json::<NewsHeadlines>()
+        .await?;
+
+    Ok(res)
+}
+
+fn headlines_queries<'a>(
+    category: &'a MarketType,
+    area: Option<&'a NewsArea>,
+    section: Option<&'a NewsSection>,
+) -> Vec<(&'static str, &'a str)> {
     let mut queries = vec![
-        ("category", category),
+        ("category", get_news_category(category)),
         ("client", "web"),
         ("lang", "en"),
         ("streaming", "false"),
@@ -64,12 +77,45 @@ pub async fn list_news(
     if let Some(section) = section {
         queries.push(("section", get_news_section(section)));
     }
-    let res = get(client, &format!("{BASE_NEWS_URL}/headlines"), &queries)
-        .await?
-        .json::<NewsHeadlines>()
-        .await?;
+    queries
+}
 
-    Ok(res)
+/// Like [`list_news`] for the `market` headlines, narrowed by `filter`. Its `section` is
+/// requested from the endpoint; the window and `limit` only cut down the one batch of
+/// headlines the endpoint returns, see [`NewsFilter`]. Pass the `published` time of the
+/// last item as the next `before` to page further back through that batch.
+pub async fn list_news_filtered(
+    client: Option<&UserCookies>,
+    market: &MarketType,
+    area: Option<&NewsArea>,
+    filter: &NewsFilter,
+) -> Result<NewsPage> {
+    let headlines = list_news(client, market, area, filter.section.as_ref()).await?;
+    Ok(headlines.filter(filter))
+}
+
+impl NewsHeadlines {
+    /// Items inside the `filter` window, newest first, cut to its `limit`. `section` is
+    /// not checked, headlines do not carry it.
+    pub fn filter(&self, filter: &NewsFilter) -> NewsPage {
+        let mut items: Vec<News> = self
+            .items
+            .iter()
+            .filter(|news| filter.before.is_none_or(|before| news.published < before))
+            .filter(|news| filter.after.is_none_or(|after| news.published >= after))
+            .cloned()
+            .collect();
+        items.sort_by_key(|news| std::cmp::Reverse(news.published));
+        let remaining = match filter.limit {
+            Some(limit) if items.len() > limit => {
+                let remaining = items.len() - limit;
+                items.truncate(limit);
+                remaining
+            }
+            _ => 0,
+        };
+        NewsPage { items, remaining }
+    }
 }
 
 async fn fetch_news(id: &str) -> Result<NewsContent> {
@@ -114,6 +160,46 @@ impl News {
     }
 }
 
+#[test]
+fn test_filter_news_window() {
+    let path = std::env::current_dir()
+        .unwrap()
+        .join("tests/data/news_headlines.json");
+    let headlines: NewsHeadlines =
+        serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+    let (after, before) = (1_699_982_000, 1_699_996_400);
+
+    let page = headlines.filter(&NewsFilter::default().after(after).before(before).limit(2));
+    assert_eq!(page.items.len(), 2);
+    assert_eq!(page.remaining, 2);
+    assert!(page
+        .items
+        .iter()
+        .all(|news| news.published >= after && news.published < before));
+    assert!(page.items[0].published > page.items[1].published);
+
+    let next = headlines.filter(
+        &NewsFilter::default()
+            .after(after)
+            .before(page.items[1].published)
+            .limit(2),
+    );
+    assert_eq!(next.items.len(), 2);
+    assert_eq!(next.remaining, 0);
+    assert!(next.items.iter().all(|news| news.published >= after));
+}
+
+#[test]
+fn test_headlines_queries_send_section() {
+    let filter = NewsFilter::default().section(NewsSection::FinancialStatement);
+    let queries = headlines_queries(&MarketType::All, None, filter.section.as_ref());
+    assert!(queries.contains(&("category", "base")));
+    assert!(queries.contains(&("section", "financial_statement")));
+    assert!(!headlines_queries(&MarketType::All, None, None)
+        .iter()
+        .any(|(key, _)| *key == "section"));
+}
+
 #[tokio::test]
 async fn test_list_news() -> Result<()> {
     let res = list_news(
@@ -0,0 +1,129 @@
+use crate::{
+    utils::build_request, Error, Result, Screener, ScreenerColumn, ScreenerFilter,
+    ScreenerResponse, ScreenerRow,
+};
+use serde_json::json;
+use tracing::debug;
+
+static SCANNER_BASE_URL: &str = "https://scanner.tradingview.com";
+
+/// Runs a TradingView screener query and returns up to 100 rows.
+///
+/// # Arguments
+///
+/// * `screener` - The market to scan, e.g. `Screener::America`.
+/// * `filters` - Conditions every row must satisfy, see [`ScreenerFilter`].
+/// * `columns` - The columns to fetch, only these are populated on the returned rows.
+///
+/// # Returns
+///
+/// A `Result` containing the matching rows, or `Error::NoScanDataFound` if the scanner returned no data.
+#[tracing::instrument]
+pub async fn run_screener(
+    screener: Screener,
+    filters: &[ScreenerFilter],
+    columns: &[ScreenerColumn],
+) -> Result<Vec<ScreenerRow>> {
+    let body = json!({
+        "filter": filters,
+        "columns": columns.iter().map(|c| c.to_string()).collect::<Vec<String>>(),
+        "options": { "lang": "en" },
+        "range": [0, 100],
+    });
+    let resp: ScreenerResponse = build_request(None)?
+        .post(format!("{SCANNER_BASE_URL}/{screener}/scan"))
+        .json(&body)
+        .send()
+        .await?
+        .json()
+        .await?;
+    debug!("screener returned {} rows in total", resp.total_count);
+    parse_screener_rows(columns, resp)
+}
+
+/// Maps the positional `d` values of a scanner response back onto the requested columns.
+pub fn parse_screener_rows(
+    columns: &[ScreenerColumn],
+    resp: ScreenerResponse,
+) -> Result<Vec<ScreenerRow>> {
+    let data = resp.data.ok_or(Error::NoScanDataFound)?;
+    let rows = data
+        .into_iter()
+        .map(|raw| {
+            let mut row = ScreenerRow {
+                symbol: raw.symbol,
+                ..Default::default()
+            };
+            for (column, value) in columns.iter().zip(raw.values.iter()) {
+                match column {
+                    ScreenerColumn::Name => row.name = value.as_str().map(String::from),
+                    ScreenerColumn::Description => {
+                        row.description = value.as_str().map(String::from)
+                    }
+                    ScreenerColumn::Price => row.price = value.as_f64(),
+                    ScreenerColumn::Change => row.change = value.as_f64(),
+                    ScreenerColumn::Volume => row.volume = value.as_f64(),
+                    ScreenerColumn::MarketCap => row.market_cap = value.as_f64(),
+                }
+            }
+            row
+        })
+        .collect();
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FilterOperation;
+
+    #[test]
+    fn test_parse_screener_rows() {
+        let current_dir = std::env::current_dir().unwrap().display().to_string();
+        let raw =
+            std::fs::read_to_string(format!("{}/tests/data/scanner_response.json", current_dir))
+                .unwrap();
+        let resp: ScreenerResponse = serde_json::from_str(&raw).unwrap();
+        let columns = [
+            ScreenerColumn::Name,
+            ScreenerColumn::Price,
+            ScreenerColumn::Change,
+            ScreenerColumn::Volume,
+            ScreenerColumn::MarketCap,
+        ];
+        let rows = parse_screener_rows(&columns, resp).unwrap();
+
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].symbol, "NASDAQ:NVDA");
+        assert_eq!(rows[0].name.as_deref(), Some("NVDA"));
+        assert_eq!(rows[0].price, Some(135.58));
+        assert_eq!(rows[0].change, Some(4.25));
+        assert_eq!(rows[0].volume, Some(248_321_540.0));
+        assert_eq!(rows[0].market_cap, Some(3_325_827_000_000.0));
+        assert_eq!(rows[2].market_cap, None);
+        assert_eq!(rows[2].description, None);
+    }
+
+    #[test]
+    fn test_parse_screener_rows_without_data() {
+        let resp: ScreenerResponse = serde_json::from_str(r#"{"totalCount":0}"#).unwrap();
+        assert!(matches!(
+            parse_screener_rows(&[ScreenerColumn::Price], resp),
+            Err(Error::NoScanDataFound)
+        ));
+    }
+
+    #[test]
+    fn test_filter_serialization() {
+        let filter = ScreenerFilter::new("change", FilterOperation::Greater, 5);
+        assert_eq!(
+            serde_json::to_value(&filter).unwrap(),
+            json!({ "left": "change", "operation": "greater", "right": 5 })
+        );
+        let filter = ScreenerFilter::new("type", FilterOperation::NotEqual, "fund");
+        assert_eq!(
+            serde_json::to_value(&filter).unwrap()["operation"],
+            json!("nequal")
+        );
+    }
+}
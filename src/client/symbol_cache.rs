@@ -0,0 +1,337 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    future::Future,
+    hash::Hash,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    client::{fundamentals, misc},
+    clock::{Clock, SystemClock},
+    DetailedSymbol, Result, Symbol, SymbolSearch, SymbolSearchResponse,
+};
+
+pub const DEFAULT_SYMBOL_CACHE_SIZE: usize = 256;
+pub const DEFAULT_SYMBOL_CACHE_TTL: Duration = Duration::from_secs(300);
+
+type CacheKey = (String, String);
+
+/// Least-recently-used cache in front of the symbol lookups, with entries expiring `ttl`
+/// after they were fetched. Resolved and detailed symbols are keyed by `(search, exchange)`,
+/// search pages by their query. Each kind holds at most `capacity` entries.
+///
+/// The cache belongs to the caller: keep one per session, or share it behind a mutex.
+///
+/// ```no_run
+/// # async fn run() -> tradingview::Result<()> {
+/// use tradingview::client::symbol_cache::SymbolCache;
+///
+/// let mut cache = SymbolCache::default();
+/// let first = cache.search_one_symbol("AAPL", "NASDAQ").await?;
+/// // served from the cache, no request is sent
+/// let second = cache.search_one_symbol("AAPL", "NASDAQ").await?;
+/// assert_eq!(first, second);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct SymbolCache {
+    capacity: usize,
+    ttl: Duration,
+    symbols: Lru<CacheKey, Symbol>,
+    details: Lru<CacheKey, DetailedSymbol>,
+    searches: Lru<String, SymbolSearchResponse>,
+    clock: Arc<dyn Clock>,
+}
+
+impl Default for SymbolCache {
+    fn default() -> Self {
+        SymbolCache::new(DEFAULT_SYMBOL_CACHE_SIZE, DEFAULT_SYMBOL_CACHE_TTL)
+    }
+}
+
+impl SymbolCache {
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        SymbolCache {
+            capacity,
+            ttl,
+            symbols: Lru::default(),
+            details: Lru::default(),
+            searches: Lru::default(),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Uses `clock` instead of the system clock to expire entries.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Number of cached entries of every kind.
+    pub fn len(&self) -> usize {
+        self.symbols.len() + self.details.len() + self.searches.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the cached symbol if present and not expired, marking it as recently used.
+    pub fn get(&mut self, search: &str, exchange: &str) -> Option<Symbol> {
+        let key = (search.to_string(), exchange.to_string());
+        self.symbols.get(&key, self.clock.now(), self.ttl)
+    }
+
+    pub fn insert(&mut self, search: &str, exchange: &str, symbol: Symbol) {
+        let key = (search.to_string(), exchange.to_string());
+        self.symbols
+            .insert(key, symbol, self.clock.now(), self.capacity);
+    }
+
+    pub fn clear(&mut self) {
+        self.symbols.clear();
+        self.details.clear();
+        self.searches.clear();
+    }
+
+    /// Cached [`search_one_symbol`](crate::client::misc::search_one_symbol).
+    pub async fn search_one_symbol(&mut self, search: &str, exchange: &str) -> Result<Symbol> {
+        self.symbol_or_fetch(search, exchange, || {
+            misc::search_one_symbol(search, exchange)
+        })
+        .await
+    }
+
+    /// Cached [`get_symbol_detailed`](crate::client::fundamentals::get_symbol_detailed). A
+    /// miss resolves the symbol through [`SymbolCache::search_one_symbol`].
+    pub async fn get_symbol_detailed(
+        &mut self,
+        symbol: &str,
+        exchange: &str,
+    ) -> Result<DetailedSymbol> {
+        let key = (symbol.to_string(), exchange.to_string());
+        if let Some(detailed) = self.details.get(&key, self.clock.now(), self.ttl) {
+            return Ok(detailed);
+        }
+        let found = self.search_one_symbol(symbol, exchange).await?;
+        let detailed = fundamentals::symbol_details(found).await?;
+        self.details
+            .insert(key, detailed.clone(), self.clock.now(), self.capacity);
+        Ok(detailed)
+    }
+
+    /// Cached [`SymbolSearch::send`], keyed by the search query so every page of a search
+    /// is its own entry.
+    pub async fn advanced_search_symbol(
+        &mut self,
+        search: &SymbolSearch,
+    ) -> Result<SymbolSearchResponse> {
+        self.search_or_fetch(search, || search.send()).await
+    }
+
+    /// Returns the symbol from the cache or resolves it with `fetch`, caching successful
+    /// results.
+    pub(crate) async fn symbol_or_fetch<F, Fut>(
+        &mut self,
+        search: &str,
+        exchange: &str,
+        fetch: F,
+    ) -> Result<Symbol>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Symbol>>,
+    {
+        if let Some(symbol) = self.get(search, exchange) {
+            return Ok(symbol);
+        }
+        let symbol = fetch().await?;
+        self.insert(search, exchange, symbol.clone());
+        Ok(symbol)
+    }
+
+    pub(crate) async fn search_or_fetch<F, Fut>(
+        &mut self,
+        search: &SymbolSearch,
+        fetch: F,
+    ) -> Result<SymbolSearchResponse>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<SymbolSearchResponse>>,
+    {
+        let key = search.query();
+        if let Some(page) = self.searches.get(&key, self.clock.now(), self.ttl) {
+            return Ok(page);
+        }
+        let page = fetch().await?;
+        self.searches
+            .insert(key, page.clone(), self.clock.now(), self.capacity);
+        Ok(page)
+    }
+}
+
+/// Entries of one kind with their fetch time, in least-recently-used order.
+#[derive(Debug)]
+struct Lru<K, V> {
+    entries: HashMap<K, (V, Instant)>,
+    order: VecDeque<K>,
+}
+
+impl<K, V> Default for Lru<K, V> {
+    fn default() -> Self {
+        Lru {
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+}
+
+impl<K: Clone + Eq + Hash, V: Clone> Lru<K, V> {
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn get(&mut self, key: &K, now: Instant, ttl: Duration) -> Option<V> {
+        let (value, fetched_at) = self.entries.get(key)?;
+        if now.duration_since(*fetched_at) >= ttl {
+            self.remove(key);
+            return None;
+        }
+        let value = value.clone();
+        self.touch(key.clone());
+        Some(value)
+    }
+
+    fn insert(&mut self, key: K, value: V, now: Instant, capacity: usize) {
+        if capacity == 0 {
+            return;
+        }
+        self.entries.insert(key.clone(), (value, now));
+        self.touch(key);
+        while self.order.len() > capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.entries.remove(&oldest);
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+    }
+
+    fn touch(&mut self, key: K) {
+        self.order.retain(|k| *k != key);
+        self.order.push_back(key);
+    }
+
+    fn remove(&mut self, key: &K) {
+        self.entries.remove(key);
+        self.order.retain(|k| k != key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn symbol(name: &str) -> Symbol {
+        Symbol {
+            symbol: name.to_string(),
+            exchange: "NASDAQ".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_symbol_or_fetch_within_ttl() {
+        let mut cache = SymbolCache::default();
+        let fetches = AtomicUsize::new(0);
+        let fetch = || async {
+            fetches.fetch_add(1, Ordering::SeqCst);
+            Ok(symbol("AAPL"))
+        };
+
+        let first = cache
+            .symbol_or_fetch("AAPL", "NASDAQ", fetch)
+            .await
+            .unwrap();
+        let second = cache
+            .symbol_or_fetch("AAPL", "NASDAQ", fetch)
+            .await
+            .unwrap();
+        assert_eq!(first, second);
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+
+        let failed = cache
+            .symbol_or_fetch("MISSING", "NASDAQ", || async {
+                Err(crate::Error::NoSearchDataFound)
+            })
+            .await;
+        assert!(failed.is_err());
+        assert!(cache.get("MISSING", "NASDAQ").is_none());
+
+        cache.clear();
+        cache
+            .symbol_or_fetch("AAPL", "NASDAQ", fetch)
+            .await
+            .unwrap();
+        assert_eq!(fetches.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_search_or_fetch_keys_pages_by_query() {
+        let mut cache = SymbolCache::default();
+        let fetches = AtomicUsize::new(0);
+        let fetch = || async {
+            fetches.fetch_add(1, Ordering::SeqCst);
+            Ok(SymbolSearchResponse {
+                remaining: 0,
+                symbols: vec![symbol("AAPL")],
+            })
+        };
+
+        let first_page = SymbolSearch::new("AAPL").limit(50);
+        let second_page = first_page.clone().start(50);
+        cache.search_or_fetch(&first_page, fetch).await.unwrap();
+        cache.search_or_fetch(&first_page, fetch).await.unwrap();
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+
+        cache.search_or_fetch(&second_page, fetch).await.unwrap();
+        assert_eq!(fetches.load(Ordering::SeqCst), 2);
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    fn test_symbol_cache_expires_with_mock_clock() {
+        let clock = MockClock::new();
+        let mut cache =
+            SymbolCache::new(8, Duration::from_secs(300)).with_clock(Arc::new(clock.clone()));
+        cache.insert("AAPL", "NASDAQ", symbol("AAPL"));
+
+        clock.advance(Duration::from_secs(299));
+        assert!(cache.get("AAPL", "NASDAQ").is_some());
+        clock.advance(Duration::from_secs(1));
+        assert!(cache.get("AAPL", "NASDAQ").is_none());
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_symbol_cache_evicts_least_recently_used() {
+        let mut cache = SymbolCache::new(2, Duration::from_secs(60));
+        cache.insert("AAPL", "NASDAQ", symbol("AAPL"));
+        cache.insert("MSFT", "NASDAQ", symbol("MSFT"));
+        assert!(cache.get("AAPL", "NASDAQ").is_some());
+
+        cache.insert("NVDA", "NASDAQ", symbol("NVDA"));
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get("MSFT", "NASDAQ").is_none());
+        assert!(cache.get("AAPL", "NASDAQ").is_some());
+
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+}
@@ -1,30 +1,60 @@
 use crate::{
     callback::Callbacks,
     chart::{
-        models::{ChartResponseData, StudyResponseData, SymbolInfo},
-        ChartOptions, StudyOptions,
+        detect_downgrade, drop_unclosed_bar,
+        models::{
+            ChartResponseData, DataPoint, IntervalDowngrade, ReplayInstanceId,
+            SeriesLoadingProgress, StudyResponseData, SymbolInfo,
+        },
+        ChartOptions, HistorySpec, StudyOptions, MAX_BARS_PER_REQUEST,
     },
+    clock::{Clock, SystemClock},
     error::TradingViewError,
     payload,
     pine_indicator::PineIndicator,
     quote::{
+        coalesce::QuoteCoalescer,
         models::{QuoteData, QuoteValue},
         utils::merge_quotes,
         ALL_QUOTE_FIELDS,
     },
-    socket::{DataServer, Socket, SocketMessageDe, SocketSession, TradingViewDataEvent},
+    socket::{
+        DataServer, SendStats, Socket, SocketConfig, SocketMessageDe, SocketMessageSer,
+        SocketSession, TradingViewDataEvent, GUEST_AUTH_TOKEN,
+    },
     utils::{gen_id, gen_session_id, symbol_init},
-    Error, Interval, Result, Timezone,
+    Error, Interval, Result, Timezone, UserCookies,
 };
+use futures_util::Future;
 use serde::Deserialize;
 use serde_json::Value;
-use std::collections::HashMap;
-use tracing::{debug, error, trace};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, UNIX_EPOCH},
+};
+use tokio::task::AbortHandle;
+use tokio_tungstenite::tungstenite::Message;
+use tracing::{debug, error, trace, warn};
 
-#[derive(Clone, Default)]
+#[derive(Clone)]
 pub struct WebSocketClient<'a> {
     metadata: Metadata,
     callbacks: Callbacks<'a>,
+    clock: Arc<dyn Clock>,
+}
+
+impl Default for WebSocketClient<'_> {
+    fn default() -> Self {
+        Self {
+            metadata: Metadata::default(),
+            callbacks: Callbacks::default(),
+            clock: Arc::new(SystemClock),
+        }
+    }
 }
 
 #[derive(Default, Clone)]
@@ -35,12 +65,65 @@ struct Metadata {
     studies: HashMap<String, String>,
     quotes: HashMap<String, QuoteValue>,
     quote_session: String,
+    quote_symbols: Vec<String>,
+    /// The full list last sent with `quote_fast_symbols`, without duplicates.
+    fast_symbols: Vec<String>,
+    replay_instances: HashMap<String, ReplayInstanceId>,
+    /// Latest replay time of each replay session in epoch seconds, from `replay_reset` and
+    /// `replay_point`.
+    replay_positions: HashMap<String, i64>,
+    /// Series whose symbol failed to resolve, re-resolved by `refresh_auth`.
+    failed_series: HashSet<String>,
+    quote_coalescer: Option<QuoteCoalescer>,
+    /// Exchange timezone of the symbol last resolved on each chart session.
+    symbol_timezones: HashMap<String, String>,
+    /// Series whose `bar_count` exceeds [`MAX_BARS_PER_REQUEST`] and is still being loaded.
+    history_requests: HashMap<String, HistoryRequest>,
+    /// Latest version of the forming bar held back from each `exclude_unclosed` series.
+    unclosed_bars: HashMap<String, DataPoint>,
+}
+
+/// A large `bar_count` loaded one `request_more_data` chunk at a time, the next chunk being
+/// requested when the series completes the previous one.
+#[derive(Debug, Clone)]
+struct HistoryRequest {
+    chart_session: String,
+    /// Bars still to request after the chunk in flight.
+    remaining: u64,
+    /// Size of the chunk in flight.
+    requested: u64,
+    /// Bars received for the chunk in flight.
+    received: u64,
 }
 
 #[derive(Clone)]
 pub struct WebSocket<'a> {
     client: WebSocketClient<'a>,
     socket: SocketSession,
+    idle_close: Option<IdleClose>,
+    auto_ping: Option<Arc<AutoPing>>,
+}
+
+/// The opt-in task sending websocket pings, aborted once the last clone of its
+/// [`WebSocket`] is dropped.
+struct AutoPing {
+    interval: Duration,
+    task: AbortHandle,
+}
+
+impl Drop for AutoPing {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// State of the opt-in auto-close of connections without subscriptions.
+#[derive(Clone)]
+struct IdleClose {
+    after: Duration,
+    /// Bumped on every subscription change so a pending close can tell it is stale.
+    generation: Arc<AtomicU64>,
+    closed: Arc<AtomicBool>,
 }
 
 #[derive(Default)]
@@ -48,12 +131,35 @@ pub struct WebSocketBuilder<'a> {
     client: Option<WebSocketClient<'a>>,
     auth_token: Option<String>,
     server: Option<DataServer>,
+    socket_config: SocketConfig,
+    idle_close_after: Option<Duration>,
+    coalesce_quotes: Option<Duration>,
+    auto_ping: Option<Duration>,
 }
 
 #[derive(Debug, Clone, Default)]
 pub struct SeriesInfo {
     pub chart_session: String,
+    pub symbol_series_id: String,
+    pub series_version: String,
     pub options: ChartOptions,
+    /// Set once a history load looks coarser than `options.interval`, see
+    /// [`detect_downgrade`](crate::chart::detect_downgrade).
+    pub actual_interval: Option<Interval>,
+}
+
+/// What a [`WebSocket`] is currently subscribed to, see [`WebSocket::active_sessions`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SessionSnapshot {
+    /// Empty until `create_quote_session` is called.
+    pub quote_session: String,
+    pub quote_symbols: Vec<String>,
+    /// Chart sessions with at least one series, sorted.
+    pub chart_sessions: Vec<String>,
+    /// `(series_id, symbol)` pairs, sorted by series id.
+    pub series: Vec<(String, String)>,
+    /// `(script_id, study_id)` pairs, sorted by study id.
+    pub studies: Vec<(String, String)>,
 }
 
 impl<'a> WebSocketBuilder<'a> {
@@ -67,21 +173,69 @@ impl<'a> WebSocketBuilder<'a> {
         self
     }
 
+    /// Uses the `auth_token` of a logged in user. Cookies without a token leave the
+    /// builder untouched, so the connection falls back to the unauthorized token.
+    pub fn user(mut self, user: &UserCookies) -> Self {
+        if !user.auth_token.is_empty() {
+            self.auth_token = Some(user.auth_token.clone());
+        }
+        self
+    }
+
+    /// Pins the data server. When unset, `build` uses [`DataServer::fastest`].
     pub fn server(mut self, server: DataServer) -> Self {
         self.server = Some(server);
         self
     }
 
+    /// Disables Nagle's algorithm on the connection, off by default.
+    pub fn tcp_nodelay(mut self, tcp_nodelay: bool) -> Self {
+        self.socket_config.tcp_nodelay = tcp_nodelay;
+        self
+    }
+
+    /// Enables TCP keepalive probes after `interval` of idle time, off by default.
+    pub fn keepalive(mut self, interval: Duration) -> Self {
+        self.socket_config.keepalive = Some(interval);
+        self
+    }
+
+    /// Closes the connection once it has had no quote symbols or series for `idle`.
+    /// The next `add_symbols` or `set_market` reconnects. Off by default.
+    pub fn auto_close_after(mut self, idle: Duration) -> Self {
+        self.idle_close_after = Some(idle);
+        self
+    }
+
+    /// Emits at most one merged quote per symbol per `interval` to `on_quote_data`.
+    /// Off by default, see [`WebSocket::set_quote_coalescing`].
+    pub fn coalesce_quotes(mut self, interval: Duration) -> Self {
+        self.coalesce_quotes = Some(interval);
+        self
+    }
+
+    /// Sends a websocket ping every `interval`, for proxies that drop connections without
+    /// client traffic. Off by default, see [`WebSocket::set_auto_ping`].
+    pub fn auto_ping(mut self, interval: Duration) -> Self {
+        self.auto_ping = Some(interval);
+        self
+    }
+
     pub async fn build(self) -> Result<WebSocket<'a>> {
-        let auth_token = self
-            .auth_token
-            .unwrap_or("unauthorized_user_token".to_string());
-        let server = self.server.unwrap_or_default();
+        let auth_token = self.auth_token.unwrap_or(GUEST_AUTH_TOKEN.to_string());
+        let server = match self.server {
+            Some(server) => server,
+            None => DataServer::fastest().await,
+        };
 
-        let socket = SocketSession::new(server, auth_token).await?;
+        let socket = SocketSession::new_with_config(server, auth_token, self.socket_config).await?;
         let client = self.client.unwrap_or_default();
 
-        Ok(WebSocket::new_with_session(client, socket))
+        let mut websocket = WebSocket::new_with_session(client, socket);
+        websocket.set_auto_close(self.idle_close_after);
+        websocket.set_quote_coalescing(self.coalesce_quotes);
+        websocket.set_auto_ping(self.auto_ping);
+        Ok(websocket)
     }
 }
 
@@ -91,8 +245,214 @@ impl<'a> WebSocket<'a> {
         WebSocketBuilder::default()
     }
 
+    /// A builder for an unauthenticated connection using [`GUEST_AUTH_TOKEN`].
+    ///
+    /// Guests get delayed quotes and a limited history depth from TradingView, and
+    /// second-resolution intervals are not available at all: `set_market` rejects them
+    /// with [`Error::GuestNotPermitted`] instead of waiting for a series that never loads.
+    pub fn guest() -> WebSocketBuilder<'a> {
+        WebSocketBuilder::default().auth_token(GUEST_AUTH_TOKEN)
+    }
+
+    /// Whether the connection is unauthenticated, see [`WebSocket::guest`].
+    pub fn is_guest(&self) -> bool {
+        self.socket.is_guest()
+    }
+
+    /// Send counts and the latest send failure of the underlying socket.
+    pub fn send_stats(&self) -> SendStats {
+        self.socket.send_stats()
+    }
+
     pub fn new_with_session(client: WebSocketClient<'a>, socket: SocketSession) -> Self {
-        Self { client, socket }
+        Self {
+            client,
+            socket,
+            idle_close: None,
+            auto_ping: None,
+        }
+    }
+
+    /// Enables or disables closing the connection after `idle` without subscriptions.
+    pub fn set_auto_close(&mut self, idle: Option<Duration>) {
+        self.idle_close = idle.map(|after| IdleClose {
+            after,
+            generation: Arc::new(AtomicU64::new(0)),
+            closed: Arc::new(AtomicBool::new(false)),
+        });
+    }
+
+    /// Enables or disables sending a websocket ping every `interval`. Pongs from the server
+    /// are only logged; a failed ping shows up in [`WebSocket::send_stats`].
+    pub fn set_auto_ping(&mut self, interval: Option<Duration>) {
+        self.auto_ping = interval.map(|interval| {
+            let clock = self.client.clock.clone();
+            let mut socket = self.socket.clone();
+            let mut sleep = clock.sleep(interval);
+            let task = tokio::spawn(async move {
+                loop {
+                    sleep.await;
+                    sleep = clock.sleep(interval);
+                    if let Err(e) = socket.ping(&Message::Ping(Vec::new())).await {
+                        debug!("auto ping failed: {}", e);
+                    }
+                }
+            });
+            Arc::new(AutoPing {
+                interval,
+                task: task.abort_handle(),
+            })
+        });
+    }
+
+    /// Whether the connection was closed for being idle and will reopen on the next subscription.
+    pub fn is_idle_closed(&self) -> bool {
+        self.idle_close
+            .as_ref()
+            .is_some_and(|idle| idle.closed.load(Ordering::SeqCst))
+    }
+
+    /// Enables or disables quote coalescing. Ticks are still merged into the quote state
+    /// while a symbol is held back; its latest state is emitted with the next tick after
+    /// `interval` has passed, or by [`WebSocket::flush_quotes`].
+    pub fn set_quote_coalescing(&mut self, interval: Option<Duration>) {
+        self.client.metadata.quote_coalescer = interval.map(QuoteCoalescer::new);
+    }
+
+    /// Emits the merged quote of every symbol held back by quote coalescing.
+    pub async fn flush_quotes(&mut self) {
+        let now = self.client.clock.now();
+        let pending = match self.client.metadata.quote_coalescer.as_mut() {
+            Some(coalescer) => coalescer.flush(now),
+            None => return,
+        };
+        self.client.emit_quotes(&pending).await;
+    }
+
+    /// A snapshot of the quote session, chart sessions, series and studies this client
+    /// has set up and not removed.
+    pub fn active_sessions(&self) -> SessionSnapshot {
+        let metadata = &self.client.metadata;
+        let mut chart_sessions: Vec<String> = metadata
+            .series
+            .values()
+            .map(|info| info.chart_session.clone())
+            .collect();
+        chart_sessions.sort();
+        chart_sessions.dedup();
+        let mut series: Vec<(String, String)> = metadata
+            .series
+            .iter()
+            .map(|(id, info)| (id.clone(), info.options.symbol.clone()))
+            .collect();
+        series.sort();
+        let mut studies: Vec<(String, String)> = metadata
+            .studies
+            .iter()
+            .map(|(script, study)| (script.clone(), study.clone()))
+            .collect();
+        studies.sort_by(|a, b| a.1.cmp(&b.1));
+        SessionSnapshot {
+            quote_session: metadata.quote_session.clone(),
+            quote_symbols: metadata.quote_symbols.clone(),
+            chart_sessions,
+            series,
+            studies,
+        }
+    }
+
+    /// Routes chart data of `series_id` to `f`, leaving other series on the global
+    /// `on_chart_data`. The handler is dropped with `remove_series` or `reset`.
+    pub fn on_series_chart_data<Fut>(
+        &mut self,
+        series_id: &str,
+        f: impl Fn((ChartOptions, Vec<DataPoint>)) -> Fut + Send + Sync + 'a,
+    ) -> &mut Self
+    where
+        Fut: Future<Output = ()> + Send + 'a,
+    {
+        let callbacks = std::mem::take(&mut self.client.callbacks);
+        self.client.callbacks = callbacks.on_series_chart_data(series_id, f);
+        self
+    }
+
+    /// Id of the series created by the latest `set_market`, e.g. `"sds_2"`.
+    pub fn last_series_id(&self) -> Option<String> {
+        let count = self.client.metadata.series_count;
+        (count > 0).then(|| format!("sds_{}", count))
+    }
+
+    /// The instance id TradingView assigned to `replay_session`, once announced.
+    pub fn replay_instance_id(&self, replay_session: &str) -> Option<&ReplayInstanceId> {
+        self.client.metadata.replay_instances.get(replay_session)
+    }
+
+    /// Current replay time of `replay_session` in epoch seconds: the last `replay_point` the
+    /// server sent, or the timestamp of the last `replay_reset`. Save it to resume later with
+    /// [`replay_resume_from`](Self::replay_resume_from).
+    pub fn replay_position(&self, replay_session: &str) -> Option<i64> {
+        self.client
+            .metadata
+            .replay_positions
+            .get(replay_session)
+            .copied()
+    }
+
+    fn active_subscriptions(&self) -> usize {
+        self.client.metadata.quote_symbols.len() + self.client.metadata.series.len()
+    }
+
+    /// Cancels a pending idle close and reopens the connection if it was closed for being idle.
+    async fn resume_if_idle(&mut self) -> Result<()> {
+        let Some(idle) = &self.idle_close else {
+            return Ok(());
+        };
+        idle.generation.fetch_add(1, Ordering::SeqCst);
+        if !idle.closed.swap(false, Ordering::SeqCst) {
+            return Ok(());
+        }
+        debug!("reopening idle-closed connection");
+        self.socket.reconnect().await?;
+        // the ping task holds a handle to the closed connection
+        self.set_auto_ping(self.auto_ping.as_ref().map(|ping| ping.interval));
+        let quote_session = self.client.metadata.quote_session.clone();
+        if !quote_session.is_empty() {
+            self.send_batch(&[
+                SocketMessageSer::new("quote_create_session", payload!(quote_session.clone())),
+                set_fields_message(&quote_session),
+            ])
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Schedules closing the connection if auto-close is enabled and nothing is subscribed.
+    fn schedule_idle_close(&self) {
+        let Some(idle) = &self.idle_close else {
+            return;
+        };
+        if self.active_subscriptions() > 0 {
+            return;
+        }
+        let generation = idle.generation.fetch_add(1, Ordering::SeqCst) + 1;
+        let idle = idle.clone();
+        let mut socket = self.socket.clone();
+        let sleep = self.client.clock.sleep(idle.after);
+        tokio::spawn(async move {
+            sleep.await;
+            if idle.generation.load(Ordering::SeqCst) == generation {
+                debug!("closing connection idle for {:?}", idle.after);
+                socket.close().await.ok();
+                idle.closed.store(true, Ordering::SeqCst);
+            }
+        });
+    }
+
+    /// Sends `messages` in a single frame, in order, so nothing else the client sends lands
+    /// between them. Useful to set up many symbols or series at once.
+    pub async fn send_batch(&mut self, messages: &[SocketMessageSer]) -> Result<&mut Self> {
+        self.socket.send_batch(messages).await?;
+        Ok(self)
     }
 
     // Begin TradingView WebSocket Quote methods
@@ -116,13 +476,18 @@ impl<'a> WebSocket<'a> {
     }
 
     pub async fn set_fields(&mut self) -> Result<&mut Self> {
-        let mut quote_fields = payload![self.client.metadata.quote_session.clone().to_string()];
-        quote_fields.extend(ALL_QUOTE_FIELDS.clone().into_iter().map(Value::from));
-        self.socket.send("quote_set_fields", &quote_fields).await?;
-        Ok(self)
+        let message = set_fields_message(&self.client.metadata.quote_session);
+        self.send_batch(&[message]).await
     }
 
     pub async fn add_symbols(&mut self, symbols: Vec<&str>) -> Result<&mut Self> {
+        self.resume_if_idle().await?;
+        let quote_symbols = &mut self.client.metadata.quote_symbols;
+        for symbol in &symbols {
+            if !quote_symbols.iter().any(|s| s == symbol) {
+                quote_symbols.push(symbol.to_string());
+            }
+        }
         let mut payloads = payload![self.client.metadata.quote_session.clone()];
         payloads.extend(symbols.into_iter().map(Value::from));
         self.socket.send("quote_add_symbols", &payloads).await?;
@@ -134,17 +499,107 @@ impl<'a> WebSocket<'a> {
         Ok(self)
     }
 
+    /// Applies a new auth token to the live connection and re-resolves every series whose
+    /// symbol failed to resolve, typically because the previous token lacked permission.
+    pub async fn refresh_auth(&mut self, auth_token: &str) -> Result<&mut Self> {
+        self.socket.update_auth_token(auth_token).await?;
+        let failed: Vec<String> = self.client.metadata.failed_series.iter().cloned().collect();
+        for series_id in failed {
+            let Some(info) = self.client.metadata.series.get(&series_id).cloned() else {
+                self.client.metadata.failed_series.remove(&series_id);
+                continue;
+            };
+            debug!(
+                "re-resolving {} for series {}",
+                info.options.symbol, series_id
+            );
+            self.resolve_symbol(
+                &info.chart_session,
+                &info.symbol_series_id,
+                &info.options.symbol,
+                &info.options,
+                None,
+            )
+            .await?;
+            self.modify_series(
+                &info.chart_session,
+                &series_id,
+                &info.series_version,
+                &info.symbol_series_id,
+                &info.options,
+            )
+            .await?;
+            self.client.metadata.failed_series.remove(&series_id);
+        }
+        Ok(self)
+    }
+
+    /// Replaces the symbols receiving fast updates with `symbols`, ignoring duplicates.
     pub async fn fast_symbols(&mut self, symbols: Vec<&str>) -> Result<&mut Self> {
-        let mut payloads = payload![self.client.metadata.quote_session.clone()];
-        payloads.extend(symbols.into_iter().map(Value::from));
-        self.socket.send("quote_fast_symbols", &payloads).await?;
+        let mut fast: Vec<String> = Vec::with_capacity(symbols.len());
+        for symbol in symbols {
+            if !fast.iter().any(|s| s == symbol) {
+                fast.push(symbol.to_string());
+            }
+        }
+        self.client.metadata.fast_symbols = fast;
+        self.send_fast_symbols().await?;
+        Ok(self)
+    }
+
+    /// Adds `symbols` to or removes them from the fast update list. Nothing is sent if the
+    /// list does not change.
+    pub async fn set_fast_mode(&mut self, symbols: Vec<&str>, enabled: bool) -> Result<&mut Self> {
+        let fast = &mut self.client.metadata.fast_symbols;
+        let before = fast.len();
+        let mut changed = false;
+        if enabled {
+            for symbol in symbols {
+                if !fast.iter().any(|s| s == symbol) {
+                    fast.push(symbol.to_string());
+                    changed = true;
+                }
+            }
+        } else {
+            fast.retain(|s| !symbols.contains(&s.as_str()));
+            changed = fast.len() != before;
+        }
+        if changed {
+            self.send_fast_symbols().await?;
+        }
         Ok(self)
     }
 
+    /// Symbols currently in fast update mode.
+    pub fn fast_mode_symbols(&self) -> &[String] {
+        &self.client.metadata.fast_symbols
+    }
+
+    async fn send_fast_symbols(&mut self) -> Result<()> {
+        let mut payloads = payload![self.client.metadata.quote_session.clone()];
+        payloads.extend(
+            self.client
+                .metadata
+                .fast_symbols
+                .iter()
+                .map(|s| Value::from(s.as_str())),
+        );
+        self.socket.send("quote_fast_symbols", &payloads).await
+    }
+
     pub async fn remove_symbols(&mut self, symbols: Vec<&str>) -> Result<&mut Self> {
+        self.client
+            .metadata
+            .quote_symbols
+            .retain(|s| !symbols.contains(&s.as_str()));
+        self.client
+            .metadata
+            .fast_symbols
+            .retain(|s| !symbols.contains(&s.as_str()));
         let mut payloads = payload![self.client.metadata.quote_session.clone()];
         payloads.extend(symbols.into_iter().map(Value::from));
         self.socket.send("quote_remove_symbols", &payloads).await?;
+        self.schedule_idle_close();
         Ok(self)
     }
     // End TradingView WebSocket Quote methods
@@ -166,7 +621,16 @@ impl<'a> WebSocket<'a> {
         Ok(self)
     }
 
+    /// Switches the chart timezone of `session`. When the session's symbol is resolved and
+    /// its exchange uses another timezone than `timezone` (other than UTC), a warning is
+    /// logged, since bars are then no longer aligned to the exchange's sessions.
     pub async fn set_timezone(&mut self, session: &str, timezone: Timezone) -> Result<&mut Self> {
+        if let Some(exchange_timezone) = self.client.timezone_mismatch(session, &timezone) {
+            warn!(
+                "timezone {} does not match exchange timezone {} of chart session {}",
+                timezone, exchange_timezone, session
+            );
+        }
         self.socket
             .send("switch_timezone", &payload!(session, timezone.to_string()))
             .await?;
@@ -204,7 +668,7 @@ impl<'a> WebSocket<'a> {
                     symbol_init(
                         symbol,
                         config.adjustment.clone(),
-                        config.currency,
+                        config.currency.clone(),
                         config.session_type.clone(),
                         None
                     )?,
@@ -256,6 +720,28 @@ impl<'a> WebSocket<'a> {
         Ok(self)
     }
 
+    /// Advances a replay by `bars_per_tick` bars once every `tick`, for `ticks` ticks.
+    ///
+    /// Replay throughput is `bars_per_tick / tick`: with one-minute bars, stepping
+    /// 10 bars every second plays back ten minutes of market time per second (600x
+    /// real time), while 1 bar per 60 seconds matches real time. Use
+    /// `bars_per_tick = 1` to single-step.
+    pub async fn replay_paced(
+        &mut self,
+        session: &str,
+        series_id: &str,
+        bars_per_tick: u64,
+        tick: Duration,
+        ticks: u64,
+    ) -> Result<&mut Self> {
+        let mut interval = tokio::time::interval(tick);
+        for _ in 0..ticks {
+            interval.tick().await;
+            self.replay_step(session, series_id, bars_per_tick).await?;
+        }
+        Ok(self)
+    }
+
     pub async fn replay_stop(&mut self, session: &str, series_id: &str) -> Result<&mut Self> {
         self.socket
             .send("replay_stop", &payload!(session, series_id))
@@ -272,9 +758,24 @@ impl<'a> WebSocket<'a> {
         self.socket
             .send("replay_reset", &payload!(session, series_id, timestamp))
             .await?;
+        self.client
+            .metadata
+            .replay_positions
+            .insert(session.to_string(), timestamp);
         Ok(self)
     }
 
+    /// Moves the replay back to a position saved with
+    /// [`replay_position`](Self::replay_position), e.g. after a restart.
+    pub async fn replay_resume_from(
+        &mut self,
+        session: &str,
+        series_id: &str,
+        position: i64,
+    ) -> Result<&mut Self> {
+        self.replay_reset(session, series_id, position).await
+    }
+
     pub async fn request_more_data(
         &mut self,
         session: &str,
@@ -354,26 +855,9 @@ impl<'a> WebSocket<'a> {
         series_symbol_id: &str,
         config: &ChartOptions,
     ) -> Result<&mut Self> {
-        let range = match (&config.range, config.from, config.to) {
-            (Some(range), _, _) => range.clone(),
-            (None, Some(from), Some(to)) => format!("r,{}:{}", from, to),
-            _ => String::default(),
-        };
-        self.socket
-            .send(
-                "create_series",
-                &payload!(
-                    session,
-                    series_id,
-                    series_version,
-                    series_symbol_id,
-                    config.interval.to_string(),
-                    config.bar_count,
-                    range // |r,1626220800:1628640000|1D|5d|1M|3M|6M|YTD|12M|60M|ALL|
-                ),
-            )
-            .await?;
-        Ok(self)
+        let message =
+            create_series_message(session, series_id, series_version, series_symbol_id, config)?;
+        self.send_batch(&[message]).await
     }
 
     pub async fn modify_series(
@@ -384,11 +868,7 @@ impl<'a> WebSocket<'a> {
         series_symbol_id: &str,
         config: &ChartOptions,
     ) -> Result<&mut Self> {
-        let range = match (&config.range, config.from, config.to) {
-            (Some(range), _, _) => range.clone(),
-            (None, Some(from), Some(to)) => format!("r,{}:{}", from, to),
-            _ => String::default(),
-        };
+        let range = config.series_range()?;
         self.socket
             .send(
                 "modify_series",
@@ -399,7 +879,7 @@ impl<'a> WebSocket<'a> {
                     series_symbol_id,
                     config.interval.to_string(),
                     config.bar_count,
-                    range // |r,1626220800:1628640000|1D|5d|1M|3M|6M|YTD|12M|60M|ALL|
+                    range // |r,1626220800:1628640000|1D|5D|1M|3M|6M|YTD|12M|60M|ALL|
                 ),
             )
             .await?;
@@ -410,6 +890,11 @@ impl<'a> WebSocket<'a> {
         self.socket
             .send("remove_series", &payload!(session, series_id))
             .await?;
+        self.client.metadata.series.remove(series_id);
+        self.client.metadata.history_requests.remove(series_id);
+        self.client.metadata.unclosed_bars.remove(series_id);
+        self.client.callbacks.series_chart_data.remove(series_id);
+        self.schedule_idle_close();
         Ok(self)
     }
 
@@ -421,23 +906,9 @@ impl<'a> WebSocket<'a> {
         config: &ChartOptions,
         replay_session: Option<String>,
     ) -> Result<&mut Self> {
-        self.socket
-            .send(
-                "resolve_symbol",
-                &payload!(
-                    session,
-                    symbol_series_id,
-                    symbol_init(
-                        symbol,
-                        config.adjustment.clone(),
-                        config.currency,
-                        config.session_type.clone(),
-                        replay_session
-                    )?
-                ),
-            )
-            .await?;
-        Ok(self)
+        let message =
+            resolve_symbol_message(session, symbol_series_id, symbol, config, replay_session)?;
+        self.send_batch(&[message]).await
     }
 
     pub async fn delete(&mut self) -> Result<&mut Self> {
@@ -448,6 +919,45 @@ impl<'a> WebSocket<'a> {
         Ok(self)
     }
 
+    /// Drops every subscription but keeps the connection: deletes the chart, replay and quote
+    /// sessions, clears the client state and opens a fresh quote session with the default
+    /// fields. Callbacks and quote coalescing settings are kept.
+    pub async fn reset(&mut self) -> Result<&mut Self> {
+        let metadata = &self.client.metadata;
+        let mut chart_sessions: Vec<String> = metadata
+            .series
+            .values()
+            .map(|info| info.chart_session.clone())
+            .collect();
+        chart_sessions.sort();
+        chart_sessions.dedup();
+        let replay_sessions: Vec<String> = metadata.replay_instances.keys().cloned().collect();
+        for session in chart_sessions {
+            self.delete_chart_session_id(&session).await?;
+        }
+        for session in replay_sessions {
+            self.delete_replay_session_id(&session).await?;
+        }
+        if !self.client.metadata.quote_session.is_empty() {
+            self.delete_quote_session().await?;
+        }
+
+        let coalesce = self
+            .client
+            .metadata
+            .quote_coalescer
+            .as_ref()
+            .map(QuoteCoalescer::interval);
+        self.client.metadata = Metadata::default();
+        self.client.callbacks.series_chart_data.clear();
+        self.set_quote_coalescing(coalesce);
+
+        self.create_quote_session().await?;
+        self.set_fields().await?;
+        self.schedule_idle_close();
+        Ok(self)
+    }
+
     // End TradingView WebSocket methods
 
     pub async fn set_replay(
@@ -505,7 +1015,40 @@ impl<'a> WebSocket<'a> {
         Ok(self)
     }
 
+    /// Loads `exchange:symbol` bars described by `spec` into a new series, see
+    /// [`ChartOptions::from_history`]. Bars arrive through `on_chart_data` as with `set_market`.
+    pub async fn fetch_history(
+        &mut self,
+        symbol: &str,
+        exchange: &str,
+        interval: Interval,
+        spec: HistorySpec,
+    ) -> Result<&mut Self> {
+        let options = ChartOptions::from_history(&format!("{exchange}:{symbol}"), interval, spec);
+        self.set_market(options).await
+    }
+
+    /// Creates a chart session and series for `options`. When a series with the same options
+    /// is already active it is reused and nothing is sent, unless the options are
+    /// [`force_new`](ChartOptions::force_new).
     pub async fn set_market(&mut self, options: ChartOptions) -> Result<&mut Self> {
+        options.validate()?;
+        if self.is_guest() && options.interval.is_seconds_based() {
+            return Err(Error::GuestNotPermitted(format!(
+                "{} interval",
+                options.interval
+            )));
+        }
+        if !options.force_new {
+            let metadata = &self.client.metadata;
+            if let Some((series_id, _)) = metadata.series.iter().find(|(id, info)| {
+                info.options.same_series(&options) && !metadata.failed_series.contains(*id)
+            }) {
+                debug!("reusing series {} for {}", series_id, options.symbol);
+                return Ok(self);
+            }
+        }
+        self.resume_if_idle().await?;
         self.client.metadata.series_count += 1;
         let series_count = self.client.metadata.series_count;
         let symbol_series_id = format!("sds_sym_{}", series_count);
@@ -513,30 +1056,50 @@ impl<'a> WebSocket<'a> {
         let series_version = format!("s{}", series_count);
         let chart_session = gen_session_id("cs");
 
-        self.create_chart_session(&chart_session).await?;
+        let initial_options = ChartOptions {
+            bar_count: options.bar_count.min(MAX_BARS_PER_REQUEST),
+            ..options.clone()
+        };
+        let create_series = create_series_message(
+            &chart_session,
+            &series_id,
+            &series_version,
+            &symbol_series_id,
+            &initial_options,
+        )?;
 
         if options.replay_mode {
+            self.create_chart_session(&chart_session).await?;
             self.set_replay(&options.symbol, &options, &chart_session, &symbol_series_id)
                 .await?;
+            self.send_batch(&[create_series]).await?;
         } else {
-            self.resolve_symbol(
-                &chart_session,
-                &symbol_series_id,
-                &options.symbol,
-                &options,
-                None,
-            )
+            // one frame, so the series is never created before its symbol is resolved
+            self.send_batch(&[
+                SocketMessageSer::new("chart_create_session", payload!(chart_session.clone())),
+                resolve_symbol_message(
+                    &chart_session,
+                    &symbol_series_id,
+                    &options.symbol,
+                    &options,
+                    None,
+                )?,
+                create_series,
+            ])
             .await?;
         }
 
-        self.create_series(
-            &chart_session,
-            &series_id,
-            &series_version,
-            &symbol_series_id,
-            &options,
-        )
-        .await?;
+        if options.bar_count > initial_options.bar_count {
+            self.client.metadata.history_requests.insert(
+                series_id.clone(),
+                HistoryRequest {
+                    chart_session: chart_session.clone(),
+                    remaining: options.bar_count - initial_options.bar_count,
+                    requested: initial_options.bar_count,
+                    received: 0,
+                },
+            );
+        }
 
         if let Some(study) = &options.study_config {
             self.set_study(study, &chart_session, &series_id).await?;
@@ -544,7 +1107,10 @@ impl<'a> WebSocket<'a> {
 
         let series_info = SeriesInfo {
             chart_session,
+            symbol_series_id,
+            series_version,
             options,
+            actual_interval: None,
         };
 
         self.client.metadata.series.insert(series_id, series_info);
@@ -552,16 +1118,57 @@ impl<'a> WebSocket<'a> {
         Ok(self)
     }
 
+    /// Requests the next chunk of a large `bar_count` once `series_id` completed the previous
+    /// one. Stops when the count is met or the previous chunk returned fewer bars than
+    /// requested, i.e. the symbol has no more history.
+    async fn request_next_chunk(&mut self, series_id: &str) -> Result<()> {
+        let history_requests = &mut self.client.metadata.history_requests;
+        let Some(request) = history_requests.get_mut(series_id) else {
+            return Ok(());
+        };
+        if request.remaining == 0 || request.received < request.requested {
+            debug!(
+                "history of series {} loaded, {} bars not requested",
+                series_id, request.remaining
+            );
+            history_requests.remove(series_id);
+            return Ok(());
+        }
+        let chunk = request.remaining.min(MAX_BARS_PER_REQUEST);
+        request.remaining -= chunk;
+        request.requested = chunk;
+        request.received = 0;
+        let chart_session = request.chart_session.clone();
+        self.request_more_data(&chart_session, series_id, chunk)
+            .await?;
+        Ok(())
+    }
+
     pub async fn subscribe(&mut self) {
         self.event_loop(&mut self.socket.to_owned()).await;
     }
+
+    /// Runs the event loop until no message has been received for `idle`, or the
+    /// connection closes. Useful for one-shot fetches that would otherwise hang.
+    pub async fn subscribe_until_idle(&mut self, idle: Duration) {
+        self.event_loop_until_idle(&mut self.socket.to_owned(), idle)
+            .await;
+    }
 }
 
 #[async_trait::async_trait]
 impl<'a> Socket for WebSocket<'a> {
     async fn handle_message_data(&mut self, message: SocketMessageDe) -> Result<()> {
         let event = TradingViewDataEvent::from(message.m.to_owned());
+        let completed = match event {
+            TradingViewDataEvent::OnSeriesCompleted => message.p.get(1).and_then(Value::as_str),
+            _ => None,
+        }
+        .map(str::to_string);
         self.client.handle_events(event, &message.p).await;
+        if let Some(series_id) = completed {
+            self.request_next_chunk(&series_id).await?;
+        }
         Ok(())
     }
 
@@ -570,6 +1177,57 @@ impl<'a> Socket for WebSocket<'a> {
     }
 }
 
+fn set_fields_message(quote_session: &str) -> SocketMessageSer {
+    let mut quote_fields = payload![quote_session];
+    quote_fields.extend(ALL_QUOTE_FIELDS.clone().into_iter().map(Value::from));
+    SocketMessageSer::new("quote_set_fields", quote_fields)
+}
+
+fn resolve_symbol_message(
+    session: &str,
+    symbol_series_id: &str,
+    symbol: &str,
+    config: &ChartOptions,
+    replay_session: Option<String>,
+) -> Result<SocketMessageSer> {
+    Ok(SocketMessageSer::new(
+        "resolve_symbol",
+        payload!(
+            session,
+            symbol_series_id,
+            symbol_init(
+                symbol,
+                config.adjustment.clone(),
+                config.currency.clone(),
+                config.session_type.clone(),
+                replay_session
+            )?
+        ),
+    ))
+}
+
+fn create_series_message(
+    session: &str,
+    series_id: &str,
+    series_version: &str,
+    series_symbol_id: &str,
+    config: &ChartOptions,
+) -> Result<SocketMessageSer> {
+    let range = config.series_range()?;
+    Ok(SocketMessageSer::new(
+        "create_series",
+        payload!(
+            session,
+            series_id,
+            series_version,
+            series_symbol_id,
+            config.interval.to_string(),
+            config.bar_count,
+            range // |r,1626220800:1628640000|1D|5D|1M|3M|6M|YTD|12M|60M|ALL|
+        ),
+    ))
+}
+
 impl<'a> WebSocketClient<'a> {
     pub(crate) async fn handle_events(
         &mut self,
@@ -579,10 +1237,11 @@ impl<'a> WebSocketClient<'a> {
         match event {
             TradingViewDataEvent::OnChartData | TradingViewDataEvent::OnChartDataUpdate => {
                 trace!("received raw chart data: {:?}", message);
-                match self
-                    .handle_chart_data(&self.metadata.series, &self.metadata.studies, message)
-                    .await
-                {
+                if event == TradingViewDataEvent::OnChartData {
+                    self.count_history_bars(message);
+                    self.detect_interval_downgrades(message).await;
+                }
+                match self.handle_chart_data(message).await {
                     Ok(_) => (),
                     Err(e) => {
                         error!("chart data parsing error: {:?}", e);
@@ -590,11 +1249,21 @@ impl<'a> WebSocketClient<'a> {
                     }
                 };
             }
-            TradingViewDataEvent::OnQuoteData => self.handle_quote_data(message).await,
+            TradingViewDataEvent::OnQuoteData => {
+                if let Err(e) = self.handle_quote_data(message).await {
+                    error!("quote data parsing error: {:?}", e);
+                    (self.callbacks.on_error)(e).await;
+                }
+            }
             TradingViewDataEvent::OnSymbolResolved => {
                 match SymbolInfo::deserialize(&message[2]) {
                     Ok(s) => {
                         debug!("receive symbol info: {:?}", s);
+                        if let Some(chart_session) = message.first().and_then(Value::as_str) {
+                            self.metadata
+                                .symbol_timezones
+                                .insert(chart_session.to_string(), s.timezone.clone());
+                        }
                         (self.callbacks.on_symbol_info)(s).await;
                     }
                     Err(e) => {
@@ -603,6 +1272,101 @@ impl<'a> WebSocketClient<'a> {
                     }
                 };
             }
+            TradingViewDataEvent::OnStudyError => {
+                // [chart_session, study_id, series_id?, reason, ...]
+                let field = |i: usize| {
+                    message
+                        .get(i)
+                        .and_then(Value::as_str)
+                        .unwrap_or_default()
+                        .to_string()
+                };
+                let reason = field(3);
+                let error = Error::from_limit_error(&reason).unwrap_or(Error::StudyError {
+                    study_id: field(1),
+                    reason,
+                });
+                error!("{}", error);
+                (self.callbacks.on_error)(error).await;
+            }
+            TradingViewDataEvent::OnError(TradingViewError::SymbolError) => {
+                // [chart_session, symbol_series_id, reason]
+                let chart_session = message.first().and_then(Value::as_str);
+                let symbol_series_id = message.get(1).and_then(Value::as_str);
+                let failed = self.metadata.series.iter().find(|(_, info)| {
+                    Some(info.chart_session.as_str()) == chart_session
+                        && Some(info.symbol_series_id.as_str()) == symbol_series_id
+                });
+                let symbol = failed
+                    .map(|(_, info)| info.options.symbol.clone())
+                    .unwrap_or_default();
+                if let Some((series_id, _)) = failed {
+                    self.metadata.failed_series.insert(series_id.clone());
+                }
+                let reason = message.get(2).and_then(Value::as_str).unwrap_or_default();
+                match Error::from_symbol_error(&symbol, reason) {
+                    Some(error) => {
+                        error!("{}", error);
+                        (self.callbacks.on_error)(error).await;
+                    }
+                    None => (self.callbacks.on_other_event)((event, message.to_owned())).await,
+                }
+            }
+            TradingViewDataEvent::OnSeriesLoading => {
+                match SeriesLoadingProgress::from_message(message) {
+                    Some(mut progress) => {
+                        progress.requested = self
+                            .metadata
+                            .series
+                            .get(&progress.series_id)
+                            .map(|info| info.options.bar_count);
+                        (self.callbacks.on_series_loading)(progress).await;
+                    }
+                    None => warn!("series loading without series id: {:?}", message),
+                }
+                (self.callbacks.on_other_event)((event, message.to_owned())).await;
+            }
+            TradingViewDataEvent::OnError(
+                TradingViewError::SeriesError
+                | TradingViewError::ProtocolError
+                | TradingViewError::CriticalError,
+            ) => match message
+                .iter()
+                .filter_map(Value::as_str)
+                .find_map(Error::from_limit_error)
+            {
+                Some(error) => {
+                    error!("{}", error);
+                    (self.callbacks.on_error)(error).await;
+                }
+                None => (self.callbacks.on_other_event)((event, message.to_owned())).await,
+            },
+            TradingViewDataEvent::OnReplayPoint => {
+                // [replay_session, request_id?, timestamp]
+                let session = message.first().and_then(Value::as_str);
+                let timestamp = message.iter().skip(1).rev().find_map(Value::as_i64);
+                if let (Some(session), Some(timestamp)) = (session, timestamp) {
+                    self.metadata
+                        .replay_positions
+                        .insert(session.to_string(), timestamp);
+                }
+                (self.callbacks.on_other_event)((event, message.to_owned())).await;
+            }
+            TradingViewDataEvent::OnReplayInstanceId => {
+                // [replay_session, instance_id]
+                let ids = Value::from(message.iter().take(2).cloned().collect::<Vec<_>>());
+                match serde_json::from_value::<(String, ReplayInstanceId)>(ids) {
+                    Ok((session, id)) => {
+                        debug!("replay session {} has instance id {:?}", session, id);
+                        self.metadata.replay_instances.insert(session, id);
+                    }
+                    Err(e) => {
+                        error!("replay instance id parsing error: {:?}", e);
+                        (self.callbacks.on_error)(Error::JsonParseError(e)).await;
+                    }
+                }
+                (self.callbacks.on_other_event)((event, message.to_owned())).await;
+            }
             _ => {
                 debug!("event: {:?}, message: {:?}", event, message);
                 (self.callbacks.on_other_event)((event, message.to_owned())).await;
@@ -610,19 +1374,60 @@ impl<'a> WebSocketClient<'a> {
         }
     }
 
-    async fn handle_chart_data(
-        &self,
-        series: &HashMap<String, SeriesInfo>,
-        studies: &HashMap<String, String>,
-        message: &[Value],
-    ) -> Result<()> {
-        for (id, s) in series.iter() {
+    /// The exchange timezone of the symbol on `chart_session` when it differs from
+    /// `timezone`. `None` for UTC, matching timezones or sessions without a resolved symbol.
+    fn timezone_mismatch(&self, chart_session: &str, timezone: &Timezone) -> Option<String> {
+        let exchange_timezone = self.metadata.symbol_timezones.get(chart_session)?;
+        let requested = timezone.to_string();
+        let mismatch = !exchange_timezone.is_empty()
+            && !matches!(timezone, Timezone::EtcUTC)
+            && *exchange_timezone != requested;
+        mismatch.then(|| exchange_timezone.clone())
+    }
+
+    async fn handle_chart_data(&mut self, message: &[Value]) -> Result<()> {
+        for (id, s) in self.metadata.series.iter() {
             debug!("received raw message - v: {:?}, m: {:?}", s, message);
             match message[1].get(id.as_str()) {
                 Some(resp_data) => {
-                    let data = ChartResponseData::deserialize(resp_data)?.series;
+                    let mut data = ChartResponseData::deserialize(resp_data)?.series;
                     debug!("series data extracted: {:?}", data);
-                    (self.callbacks.on_chart_data)((s.options.clone(), data)).await;
+                    let mut forming_only = false;
+                    if s.options.exclude_unclosed {
+                        let now = self
+                            .clock
+                            .system_time()
+                            .duration_since(UNIX_EPOCH)
+                            .unwrap_or_default()
+                            .as_secs() as i64;
+                        let forming = drop_unclosed_bar(&mut data, s.options.interval, now);
+                        if let Some(held) = self.metadata.unclosed_bars.remove(id) {
+                            // deliver the held back bar once it closed, unless the update
+                            // already carries its final version
+                            let boundary = s.options.interval.next_boundary(held.timestamp());
+                            let closed = boundary <= now
+                                || data
+                                    .iter()
+                                    .chain(&forming)
+                                    .any(|bar| bar.timestamp() >= boundary);
+                            if !closed {
+                                if forming.is_none() {
+                                    self.metadata.unclosed_bars.insert(id.clone(), held);
+                                }
+                            } else if !data.iter().any(|bar| bar.timestamp() == held.timestamp()) {
+                                let position =
+                                    data.partition_point(|bar| bar.timestamp() < held.timestamp());
+                                data.insert(position, held);
+                            }
+                        }
+                        forming_only = forming.is_some() && data.is_empty();
+                        if let Some(bar) = forming {
+                            self.metadata.unclosed_bars.insert(id.clone(), bar);
+                        }
+                    }
+                    if !forming_only {
+                        (self.callbacks.chart_data_for(id))((s.options.clone(), data)).await;
+                    }
                 }
                 None => {
                     debug!("receive empty data on series: {:?}", s);
@@ -630,13 +1435,60 @@ impl<'a> WebSocketClient<'a> {
             }
 
             if let Some(study_options) = &s.options.study_config {
-                self.handle_study_data(study_options, studies, message)
+                self.handle_study_data(study_options, &self.metadata.studies, message)
                     .await?;
             }
         }
         Ok(())
     }
 
+    /// Adds the bars of a `timescale_update` to the chunk in flight of each series loading a
+    /// large `bar_count`.
+    fn count_history_bars(&mut self, message: &[Value]) {
+        for (id, request) in self.metadata.history_requests.iter_mut() {
+            let Some(resp_data) = message.get(1).and_then(|data| data.get(id.as_str())) else {
+                continue;
+            };
+            if let Ok(data) = ChartResponseData::deserialize(resp_data) {
+                request.received += data.series.len() as u64;
+            }
+        }
+    }
+
+    /// Compares the spacing of a history load with each series' requested interval and
+    /// reports a downgrade once per series through `on_interval_downgrade`.
+    async fn detect_interval_downgrades(&mut self, message: &[Value]) {
+        let mut downgraded = Vec::new();
+        for (id, info) in self.metadata.series.iter_mut() {
+            let Some(resp_data) = message.get(1).and_then(|data| data.get(id.as_str())) else {
+                continue;
+            };
+            let Ok(data) = ChartResponseData::deserialize(resp_data) else {
+                continue;
+            };
+            let requested = info.options.interval;
+            match detect_downgrade(&data.series, requested) {
+                Some(actual) if info.actual_interval != Some(actual) => {
+                    warn!(
+                        "series {} of {} requested {} but receives {} bars",
+                        id, info.options.symbol, requested, actual
+                    );
+                    info.actual_interval = Some(actual);
+                    downgraded.push(IntervalDowngrade {
+                        series_id: id.clone(),
+                        symbol: info.options.symbol.clone(),
+                        requested,
+                        actual,
+                    });
+                }
+                _ => {}
+            }
+        }
+        for downgrade in downgraded {
+            (self.callbacks.on_interval_downgrade)(downgrade).await;
+        }
+    }
+
     async fn handle_study_data(
         &self,
         options: &StudyOptions,
@@ -653,31 +1505,1211 @@ impl<'a> WebSocketClient<'a> {
         Ok(())
     }
 
-    async fn handle_quote_data(&mut self, message: &[Value]) {
+    async fn handle_quote_data(&mut self, message: &[Value]) -> Result<()> {
         debug!("received raw quote data: {:?}", message);
-        let qsd = QuoteData::deserialize(&message[1]).unwrap_or_default();
+        let qsd = QuoteData::deserialize(message.get(1).unwrap_or(&Value::Null))?;
         if qsd.status == "ok" {
             if let Some(prev_quote) = self.metadata.quotes.get_mut(&qsd.name) {
                 *prev_quote = merge_quotes(prev_quote, &qsd.value);
             } else {
-                self.metadata.quotes.insert(qsd.name, qsd.value);
+                self.metadata.quotes.insert(qsd.name.clone(), qsd.value);
             }
 
+            if let Some(coalescer) = self.metadata.quote_coalescer.as_mut() {
+                let due = coalescer.tick(&qsd.name, self.clock.now());
+                self.emit_quotes(&due).await;
+                return Ok(());
+            }
             for q in self.metadata.quotes.values() {
                 debug!("quote data: {:?}", q);
                 (self.callbacks.on_quote_data)(q.to_owned()).await;
             }
         } else {
             error!("quote data status error: {:?}", qsd);
-            (self.callbacks.on_error)(Error::TradingViewError(
+            (self.callbacks.on_error)(Error::from_server(
                 TradingViewError::QuoteDataStatusError,
+                message,
             ))
             .await;
         }
+        Ok(())
+    }
+
+    async fn emit_quotes(&self, symbols: &[String]) {
+        for symbol in symbols {
+            if let Some(q) = self.metadata.quotes.get(symbol) {
+                debug!("quote data: {:?}", q);
+                (self.callbacks.on_quote_data)(q.to_owned()).await;
+            }
+        }
     }
 
     pub fn set_callbacks(mut self, callbacks: Callbacks<'a>) -> Self {
         self.callbacks = callbacks;
         self
     }
+
+    /// Uses `clock` instead of the system clock for quote coalescing, the idle auto-close
+    /// and telling closed bars from forming ones.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        clock::MockClock,
+        socket::testing::{connect, drain, mock_server, mock_session, record},
+    };
+    use serde_json::json;
+    use std::sync::{Arc, Mutex};
+    use tokio::sync::oneshot;
+
+    fn capture_errors<'a>() -> (WebSocketClient<'a>, Arc<Mutex<Vec<Error>>>) {
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let sink = errors.clone();
+        let callbacks = Callbacks::default().on_error(move |e| {
+            let sink = sink.clone();
+            async move { sink.lock().unwrap().push(e) }
+        });
+        (WebSocketClient::default().set_callbacks(callbacks), errors)
+    }
+
+    #[tokio::test]
+    async fn test_malformed_quote_data_emits_error() {
+        let (mut client, errors) = capture_errors();
+
+        client
+            .handle_events(
+                TradingViewDataEvent::OnQuoteData,
+                &vec![json!("qs_test"), json!({ "n": 1, "s": "ok" })],
+            )
+            .await;
+        client
+            .handle_events(TradingViewDataEvent::OnQuoteData, &vec![json!("qs_test")])
+            .await;
+
+        let errors = errors.lock().unwrap();
+        assert_eq!(errors.len(), 2);
+        assert!(errors.iter().all(|e| matches!(e, Error::JsonParseError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_quote_coalescing_limits_emissions() {
+        let quotes = Arc::new(Mutex::new(Vec::new()));
+        let sink = quotes.clone();
+        let callbacks = Callbacks::default().on_quote_data(move |q| {
+            let sink = sink.clone();
+            async move { sink.lock().unwrap().push(q) }
+        });
+        let socket = mock_session(drain).await;
+        let clock = MockClock::new();
+        let mut websocket = WebSocket::new_with_session(
+            WebSocketClient::default()
+                .set_callbacks(callbacks)
+                .with_clock(Arc::new(clock.clone())),
+            socket,
+        );
+        websocket.set_quote_coalescing(Some(Duration::from_secs(60)));
+
+        let tick = |price: i32| {
+            vec![
+                json!("qs_test"),
+                json!({ "n": "BINANCE:BTCUSDT", "s": "ok", "v": { "lp": price } }),
+            ]
+        };
+        for price in 1..=20 {
+            websocket
+                .client
+                .handle_events(TradingViewDataEvent::OnQuoteData, &tick(price))
+                .await;
+        }
+        assert_eq!(quotes.lock().unwrap().len(), 1);
+
+        websocket.flush_quotes().await;
+        assert_eq!(quotes.lock().unwrap().len(), 2);
+        assert_eq!(quotes.lock().unwrap()[1].price, Some(20.0));
+
+        clock.advance(Duration::from_secs(59));
+        websocket
+            .client
+            .handle_events(TradingViewDataEvent::OnQuoteData, &tick(21))
+            .await;
+        assert_eq!(quotes.lock().unwrap().len(), 2);
+        clock.advance(Duration::from_secs(1));
+        websocket
+            .client
+            .handle_events(TradingViewDataEvent::OnQuoteData, &tick(22))
+            .await;
+        let quotes = quotes.lock().unwrap();
+        assert_eq!(quotes.len(), 3);
+        assert_eq!(quotes[2].price, Some(22.0));
+    }
+
+    #[tokio::test]
+    async fn test_active_sessions_snapshot() {
+        let socket = mock_session(drain).await;
+        let mut websocket = WebSocket::new_with_session(WebSocketClient::default(), socket);
+        assert_eq!(websocket.active_sessions(), SessionSnapshot::default());
+
+        websocket.create_quote_session().await.unwrap();
+        websocket
+            .add_symbols(vec!["NASDAQ:AAPL", "BINANCE:BTCUSDT"])
+            .await
+            .unwrap();
+        websocket
+            .set_market(ChartOptions::new("NASDAQ:MSFT", Interval::OneMinute))
+            .await
+            .unwrap();
+        websocket
+            .client
+            .metadata
+            .studies
+            .insert("Script$STD;RSI".to_string(), "st1".to_string());
+
+        let snapshot = websocket.active_sessions();
+        assert!(snapshot.quote_session.starts_with("qs_"));
+        assert_eq!(
+            snapshot.quote_symbols,
+            vec!["NASDAQ:AAPL", "BINANCE:BTCUSDT"]
+        );
+        assert_eq!(snapshot.chart_sessions.len(), 1);
+        assert!(snapshot.chart_sessions[0].starts_with("cs_"));
+        assert_eq!(
+            snapshot.series,
+            vec![("sds_1".to_string(), "NASDAQ:MSFT".to_string())]
+        );
+        assert_eq!(
+            snapshot.studies,
+            vec![("Script$STD;RSI".to_string(), "st1".to_string())]
+        );
+
+        websocket.remove_symbols(vec!["NASDAQ:AAPL"]).await.unwrap();
+        assert_eq!(
+            websocket.active_sessions().quote_symbols,
+            vec!["BINANCE:BTCUSDT"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reset_keeps_connection() {
+        let (tx, rx) = oneshot::channel();
+        let socket = mock_session(move |s| record(s, tx)).await;
+        let mut websocket = WebSocket::new_with_session(WebSocketClient::default(), socket);
+
+        websocket.create_quote_session().await.unwrap();
+        websocket.add_symbols(vec!["NASDAQ:AAPL"]).await.unwrap();
+        websocket
+            .set_market(ChartOptions::new("NASDAQ:MSFT", Interval::OneMinute))
+            .await
+            .unwrap();
+        let before = websocket.active_sessions();
+
+        websocket.reset().await.unwrap();
+        let after = websocket.active_sessions();
+        assert!(after.quote_symbols.is_empty());
+        assert!(after.chart_sessions.is_empty());
+        assert!(after.series.is_empty());
+        assert!(after.quote_session.starts_with("qs_"));
+        assert_ne!(after.quote_session, before.quote_session);
+
+        websocket
+            .add_symbols(vec!["BINANCE:BTCUSDT"])
+            .await
+            .unwrap();
+        websocket.delete().await.unwrap();
+
+        let sent: Vec<String> = rx.await.unwrap().into_iter().map(|m| m.m).collect();
+        let reset_at = sent
+            .iter()
+            .position(|m| m == "chart_delete_session")
+            .unwrap();
+        assert_eq!(
+            sent[reset_at..],
+            [
+                "chart_delete_session",
+                "quote_delete_session",
+                "quote_create_session",
+                "quote_set_fields",
+                "quote_add_symbols",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_interval_downgrade_reported_once() {
+        let downgrades = Arc::new(Mutex::new(Vec::new()));
+        let sink = downgrades.clone();
+        let callbacks = Callbacks::default().on_interval_downgrade(move |d| {
+            let sink = sink.clone();
+            async move { sink.lock().unwrap().push(d) }
+        });
+        let mut client = WebSocketClient::default().set_callbacks(callbacks);
+        client.metadata.series.insert(
+            "sds_1".to_string(),
+            SeriesInfo {
+                options: ChartOptions::new("BINANCE:BTCUSDT", Interval::OneSecond),
+                ..Default::default()
+            },
+        );
+        let bars = |start: f64, count: usize| {
+            let bars: Vec<Value> = (0..count)
+                .map(|i| json!({ "i": i, "v": [start + 60.0 * i as f64, 1.0, 1.0, 1.0, 1.0, 1.0] }))
+                .collect();
+            let message = json!(["cs_test", { "sds_1": { "s": bars } }]);
+            serde_json::from_value::<Vec<Value>>(message).unwrap()
+        };
+
+        // `du` updates and short loads are not checked
+        client
+            .handle_events(
+                TradingViewDataEvent::OnChartDataUpdate,
+                &bars(1_700_000_000.0, 12),
+            )
+            .await;
+        client
+            .handle_events(TradingViewDataEvent::OnChartData, &bars(1_700_000_000.0, 3))
+            .await;
+        assert!(downgrades.lock().unwrap().is_empty());
+
+        for start in [1_700_000_000.0, 1_700_000_060.0] {
+            client
+                .handle_events(TradingViewDataEvent::OnChartData, &bars(start, 12))
+                .await;
+        }
+
+        let downgrades = downgrades.lock().unwrap();
+        assert_eq!(
+            *downgrades,
+            vec![IntervalDowngrade {
+                series_id: "sds_1".to_string(),
+                symbol: "BINANCE:BTCUSDT".to_string(),
+                requested: Interval::OneSecond,
+                actual: Interval::OneMinute,
+            }]
+        );
+        assert_eq!(
+            client.metadata.series["sds_1"].actual_interval,
+            Some(Interval::OneMinute)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_set_fast_mode_dedups_and_tracks() {
+        let (tx, rx) = oneshot::channel();
+        let socket = mock_session(move |s| record(s, tx)).await;
+        let mut websocket = WebSocket::new_with_session(WebSocketClient::default(), socket);
+        websocket.create_quote_session().await.unwrap();
+        let qs = websocket.client.metadata.quote_session.clone();
+        websocket.add_symbols(vec!["A", "B", "C"]).await.unwrap();
+
+        websocket
+            .set_fast_mode(vec!["A", "B", "A"], true)
+            .await
+            .unwrap();
+        websocket.set_fast_mode(vec!["A"], true).await.unwrap();
+        websocket.set_fast_mode(vec!["C"], false).await.unwrap();
+        websocket.set_fast_mode(vec!["A"], false).await.unwrap();
+        assert_eq!(websocket.fast_mode_symbols(), ["B"]);
+        websocket.fast_symbols(vec!["C", "C"]).await.unwrap();
+        assert_eq!(websocket.fast_mode_symbols(), ["C"]);
+        websocket.delete().await.unwrap();
+
+        let payloads: Vec<Vec<Value>> = rx
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|m| m.m == "quote_fast_symbols")
+            .map(|m| m.p)
+            .collect();
+        assert_eq!(
+            payloads,
+            vec![
+                vec![json!(qs), json!("A"), json!("B")],
+                vec![json!(qs), json!("B")],
+                vec![json!(qs), json!("C")],
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ping_frame_is_answered_with_pong() {
+        use futures_util::{SinkExt, StreamExt};
+        use tokio_tungstenite::tungstenite::Message;
+
+        let (tx, rx) = oneshot::channel();
+        let socket = mock_session(move |mut s| async move {
+            s.send(Message::Ping(b"heartbeat".to_vec())).await.unwrap();
+            let mut pongs = Vec::new();
+            while let Ok(Some(Ok(message))) =
+                tokio::time::timeout(Duration::from_millis(500), s.next()).await
+            {
+                if let Message::Pong(payload) = message {
+                    pongs.push(payload);
+                }
+            }
+            tx.send(pongs).ok();
+        })
+        .await;
+        let mut websocket = WebSocket::new_with_session(WebSocketClient::default(), socket);
+        tokio::time::timeout(
+            Duration::from_secs(5),
+            websocket.subscribe_until_idle(Duration::from_millis(200)),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(rx.await.unwrap(), vec![b"heartbeat".to_vec()]);
+    }
+
+    #[tokio::test]
+    async fn test_auto_ping_sends_ping_every_interval() {
+        use futures_util::StreamExt;
+        use tokio_tungstenite::tungstenite::Message;
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let socket = mock_session(move |mut s| async move {
+            while let Some(Ok(message)) = s.next().await {
+                if let Message::Ping(_) = message {
+                    tx.send(()).ok();
+                }
+            }
+        })
+        .await;
+        let clock = MockClock::new();
+        let mut websocket = WebSocket::new_with_session(
+            WebSocketClient::default().with_clock(Arc::new(clock.clone())),
+            socket,
+        );
+        websocket.set_auto_ping(Some(Duration::from_secs(30)));
+
+        clock.advance(Duration::from_secs(29));
+        let early = tokio::time::timeout(Duration::from_millis(100), rx.recv()).await;
+        assert!(early.is_err());
+
+        clock.advance(Duration::from_secs(1));
+        tokio::time::timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        clock.advance(Duration::from_secs(30));
+        tokio::time::timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+
+        websocket.set_auto_ping(None);
+        clock.advance(Duration::from_secs(30));
+        let stopped = tokio::time::timeout(Duration::from_millis(100), rx.recv()).await;
+        assert!(stopped.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_until_idle_returns_without_data() {
+        let socket = mock_session(drain).await;
+        let mut websocket = WebSocket::new_with_session(WebSocketClient::default(), socket);
+
+        tokio::time::pause();
+        let started = tokio::time::Instant::now();
+        let result = tokio::time::timeout(
+            Duration::from_secs(5),
+            websocket.subscribe_until_idle(Duration::from_millis(100)),
+        )
+        .await;
+
+        assert!(result.is_ok());
+        assert!(started.elapsed() >= Duration::from_millis(100));
+    }
+
+    async fn replayed_bars(bars_per_tick: u64, ticks: u64) -> u64 {
+        let (tx, rx) = oneshot::channel();
+        let socket = mock_session(move |s| record(s, tx)).await;
+        let mut websocket = WebSocket::new_with_session(WebSocketClient::default(), socket);
+
+        websocket
+            .replay_paced(
+                "rs_test",
+                "series",
+                bars_per_tick,
+                Duration::from_millis(5),
+                ticks,
+            )
+            .await
+            .unwrap();
+        websocket.delete().await.unwrap();
+
+        let sent = rx.await.unwrap();
+        let steps: Vec<_> = sent.iter().filter(|m| m.m == "replay_step").collect();
+        assert_eq!(steps.len() as u64, ticks);
+        steps.iter().map(|m| m.p[2].as_u64().unwrap()).sum()
+    }
+
+    #[tokio::test]
+    async fn test_replay_position_save_and_resume() {
+        let (tx, rx) = oneshot::channel();
+        let socket = mock_session(move |s| record(s, tx)).await;
+        let mut websocket = WebSocket::new_with_session(WebSocketClient::default(), socket);
+        assert_eq!(websocket.replay_position("rs_test"), None);
+
+        websocket
+            .replay_reset("rs_test", "series", 1_700_000_000)
+            .await
+            .unwrap();
+        assert_eq!(websocket.replay_position("rs_test"), Some(1_700_000_000));
+        for point in [1_700_000_060, 1_700_000_120] {
+            websocket.replay_step("rs_test", "series", 1).await.unwrap();
+            let message: SocketMessageDe = serde_json::from_value(json!({
+                "m": "replay_point",
+                "p": ["rs_test", "req_1", point]
+            }))
+            .unwrap();
+            websocket.handle_message_data(message).await.unwrap();
+        }
+        let saved = websocket.replay_position("rs_test").unwrap();
+        assert_eq!(saved, 1_700_000_120);
+
+        websocket
+            .replay_reset("rs_test", "series", 1_600_000_000)
+            .await
+            .unwrap();
+        websocket
+            .replay_resume_from("rs_test", "series", saved)
+            .await
+            .unwrap();
+        assert_eq!(websocket.replay_position("rs_test"), Some(saved));
+        websocket.delete().await.unwrap();
+
+        let sent = rx.await.unwrap();
+        let resets: Vec<_> = sent.iter().filter(|m| m.m == "replay_reset").collect();
+        assert_eq!(resets.last().unwrap().p[2], json!(saved));
+    }
+
+    #[tokio::test]
+    async fn test_replay_paced_speed() {
+        assert_eq!(replayed_bars(1, 3).await, 3);
+        assert_eq!(replayed_bars(10, 3).await, 30);
+    }
+
+    /// Serves `history` bars in total: each `create_series` or `request_more_data` gets a
+    /// `timescale_update` with as many of the remaining bars as asked for, then
+    /// `series_completed`. Reports every message the client sent.
+    async fn serve_history(
+        mut socket: tokio_tungstenite::WebSocketStream<tokio::net::TcpStream>,
+        history: u64,
+        sink: oneshot::Sender<Vec<SocketMessageDe>>,
+    ) {
+        use crate::{
+            socket::{SocketMessage, SocketMessageSer},
+            utils::parse_packet,
+        };
+        use futures_util::{SinkExt, StreamExt};
+        use tokio_tungstenite::tungstenite::Message;
+
+        let (mut received, mut served) = (Vec::new(), 0);
+        while let Some(Ok(message)) = socket.next().await {
+            let Message::Text(text) = message else {
+                continue;
+            };
+            for packet in parse_packet(&text).messages {
+                let SocketMessage::SocketMessage(m) = packet else {
+                    continue;
+                };
+                let asked = match m.m.as_str() {
+                    "create_series" => m.p[5].as_u64(),
+                    "request_more_data" => m.p[2].as_u64(),
+                    _ => None,
+                };
+                if let Some(asked) = asked {
+                    let count = asked.min(history - served);
+                    let bars: Vec<Value> = (served..served + count)
+                        .map(|i| json!({ "i": i, "v": [i * 60, 1.0, 1.0, 1.0, 1.0, 1.0] }))
+                        .collect();
+                    served += count;
+                    let (session, series_id) = (m.p[0].clone(), m.p[1].clone());
+                    for (method, payload) in [
+                        (
+                            "timescale_update",
+                            json!([session, { series_id.as_str().unwrap(): { "s": bars } }]),
+                        ),
+                        ("series_completed", json!([session, series_id, "streaming"])),
+                    ] {
+                        let reply = SocketMessageSer::new(method, payload);
+                        socket.send(reply.to_message().unwrap()).await.unwrap();
+                    }
+                }
+                received.push(m);
+            }
+        }
+        sink.send(received).ok();
+    }
+
+    async fn requested_chunks(bar_count: u64, history: u64) -> Vec<u64> {
+        let (tx, rx) = oneshot::channel();
+        let socket = mock_session(move |s| serve_history(s, history, tx)).await;
+        let mut websocket = WebSocket::new_with_session(WebSocketClient::default(), socket);
+
+        websocket
+            .set_market(
+                ChartOptions::new("BINANCE:BTCUSDT", Interval::OneMinute).bar_count(bar_count),
+            )
+            .await
+            .unwrap();
+        tokio::time::timeout(
+            Duration::from_secs(10),
+            websocket.subscribe_until_idle(Duration::from_millis(1000)),
+        )
+        .await
+        .unwrap();
+        websocket.delete().await.unwrap();
+
+        let sent = rx.await.unwrap();
+        let create_series = sent.iter().find(|m| m.m == "create_series").unwrap();
+        assert_eq!(
+            create_series.p[5],
+            json!(bar_count.min(MAX_BARS_PER_REQUEST))
+        );
+        sent.iter()
+            .filter(|m| m.m == "request_more_data")
+            .map(|m| m.p[2].as_u64().unwrap())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_set_market_chunks_large_bar_count() {
+        assert_eq!(
+            requested_chunks(50_000, 100_000).await,
+            vec![20_000, 10_000]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_set_market_chunking_stops_on_short_history() {
+        assert_eq!(requested_chunks(50_000, 300).await, Vec::<u64>::new());
+        assert_eq!(requested_chunks(50_000, 25_000).await, vec![20_000]);
+    }
+
+    #[tokio::test]
+    async fn test_quote_status_error_carries_payload() {
+        let (mut client, errors) = capture_errors();
+
+        let message = r#"{"m":"qsd","p":["qs_test",{"n":"NASDAQ:APPL","s":"error","errmsg":"invalid symbol","v":{}}]}"#;
+        let message: SocketMessageDe = serde_json::from_str(message).unwrap();
+        client
+            .handle_events(TradingViewDataEvent::from(message.m), &message.p)
+            .await;
+
+        let errors = errors.lock().unwrap();
+        match &errors[..] {
+            [error @ Error::ServerError { code, message }] => {
+                assert_eq!(*code, TradingViewError::QuoteDataStatusError);
+                assert!(message.contains(r#""errmsg":"invalid symbol""#));
+                assert!(error.to_string().contains("invalid symbol"));
+            }
+            other => panic!("unexpected errors: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_study_error_emits_error_with_study_id() {
+        let (mut client, errors) = capture_errors();
+
+        let message = r#"{"m":"study_error","p":["cs_test","st1","st1_0","invalid input: length must be > 0",{}]}"#;
+        let message: SocketMessageDe = serde_json::from_str(message).unwrap();
+        client
+            .handle_events(TradingViewDataEvent::from(message.m), &message.p)
+            .await;
+
+        let errors = errors.lock().unwrap();
+        assert_eq!(errors.len(), 1);
+        match &errors[0] {
+            Error::StudyError { study_id, reason } => {
+                assert_eq!(study_id, "st1");
+                assert_eq!(reason, "invalid input: length must be > 0");
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_auto_close_when_idle_and_reopen_on_add_symbols() {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let url = mock_server(move |socket| {
+            let tx = tx.clone();
+            async move {
+                let (done, received) = oneshot::channel();
+                record(socket, done).await;
+                tx.send(received.await.unwrap()).ok();
+            }
+        })
+        .await;
+        let clock = MockClock::new();
+        let mut websocket = WebSocket::new_with_session(
+            WebSocketClient::default().with_clock(Arc::new(clock.clone())),
+            connect(url).await,
+        );
+        websocket.set_auto_close(Some(Duration::from_secs(60)));
+
+        websocket
+            .create_quote_session()
+            .await
+            .unwrap()
+            .add_symbols(vec!["BINANCE:BTCUSDT"])
+            .await
+            .unwrap()
+            .remove_symbols(vec!["BINANCE:BTCUSDT"])
+            .await
+            .unwrap();
+
+        clock.advance(Duration::from_secs(59));
+        for _ in 0..100 {
+            tokio::task::yield_now().await;
+        }
+        assert!(!websocket.is_idle_closed());
+
+        clock.advance(Duration::from_secs(1));
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !websocket.is_idle_closed() {
+                tokio::task::yield_now().await;
+            }
+        })
+        .await
+        .unwrap();
+        let first = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(first.last().unwrap().m, "quote_remove_symbols");
+
+        websocket.add_symbols(vec!["NASDAQ:AAPL"]).await.unwrap();
+        assert!(!websocket.is_idle_closed());
+        websocket.delete().await.unwrap();
+
+        let second = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        let methods: Vec<&str> = second.iter().map(|m| m.m.as_str()).collect();
+        assert_eq!(
+            methods,
+            vec![
+                "set_auth_token",
+                "quote_create_session",
+                "quote_set_fields",
+                "quote_add_symbols"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_replay_instance_id_is_stored() {
+        let (client, errors) = capture_errors();
+        let mut websocket = WebSocket::new_with_session(client, mock_session(drain).await);
+
+        let message = r#"{"m":"replay_instance_id","p":["rs_GIbLDzDTyq3E","xg5gw9mCMIEm"]}"#;
+        let message: SocketMessageDe = serde_json::from_str(message).unwrap();
+        websocket.handle_message_data(message).await.unwrap();
+
+        assert_eq!(
+            websocket.replay_instance_id("rs_GIbLDzDTyq3E"),
+            Some(&ReplayInstanceId("xg5gw9mCMIEm".to_string()))
+        );
+        assert!(errors.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_refresh_auth_re_resolves_failed_symbol() {
+        let (tx, rx) = oneshot::channel();
+        let socket = mock_session(move |s| record(s, tx)).await;
+        let mut websocket = WebSocket::new_with_session(WebSocketClient::default(), socket);
+
+        websocket
+            .set_market(ChartOptions::new("CME_MINI:ES1!", Interval::OneMinute).bar_count(100))
+            .await
+            .unwrap();
+        let chart_session = websocket.client.metadata.series["sds_1"]
+            .chart_session
+            .clone();
+        let message: SocketMessageDe = serde_json::from_value(json!({
+            "m": "symbol_error",
+            "p": [chart_session, "sds_sym_1", "permission denied"]
+        }))
+        .unwrap();
+        websocket.handle_message_data(message).await.unwrap();
+
+        websocket.refresh_auth("rotated_token").await.unwrap();
+        websocket.delete().await.unwrap();
+
+        let sent = rx.await.unwrap();
+        let after_rotation: Vec<&SocketMessageDe> = sent
+            .iter()
+            .skip_while(|m| m.p.first() != Some(&json!("rotated_token")))
+            .collect();
+        let methods: Vec<&str> = after_rotation.iter().map(|m| m.m.as_str()).collect();
+        assert_eq!(
+            methods[..3],
+            ["set_auth_token", "resolve_symbol", "modify_series"]
+        );
+        assert_eq!(after_rotation[1].p[1], json!("sds_sym_1"));
+        assert!(websocket.client.metadata.failed_series.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_timezone_mismatch_with_symbol_info() {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        let mut websocket = WebSocket::new_with_session(
+            WebSocketClient::default(),
+            mock_session(|s| record(s, tx)).await,
+        );
+        websocket.create_chart_session("cs_test").await.unwrap();
+        assert_eq!(
+            websocket
+                .client
+                .timezone_mismatch("cs_test", &Timezone::AmericaNewYork),
+            None
+        );
+
+        let message: SocketMessageDe = serde_json::from_value(json!({
+            "m": "symbol_resolved",
+            "p": ["cs_test", "sds_sym_1", { "pro_name": "HOSE:FPT", "exchange": "HOSE", "timezone": "Asia/Ho_Chi_Minh" }]
+        }))
+        .unwrap();
+        websocket.handle_message_data(message).await.unwrap();
+
+        let mismatch = |timezone| websocket.client.timezone_mismatch("cs_test", &timezone);
+        assert_eq!(
+            mismatch(Timezone::AmericaNewYork).as_deref(),
+            Some("Asia/Ho_Chi_Minh")
+        );
+        assert_eq!(mismatch(Timezone::AsiaHoChiMinh), None);
+        assert_eq!(mismatch(Timezone::EtcUTC), None);
+
+        // the switch is still sent, only a warning is logged
+        websocket
+            .set_timezone("cs_test", Timezone::AmericaNewYork)
+            .await
+            .unwrap();
+        websocket.socket.close().await.unwrap();
+        let received = rx.await.unwrap();
+        let switch = received.iter().find(|m| m.m == "switch_timezone").unwrap();
+        assert_eq!(switch.p, vec![json!("cs_test"), json!("America/New_York")]);
+    }
+
+    #[tokio::test]
+    async fn test_exclude_unclosed_drops_forming_bar() {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let sink = received.clone();
+        let clock = MockClock::new();
+        let client = WebSocketClient::default()
+            .set_callbacks(Callbacks::default().on_chart_data(
+                move |(options, data): (ChartOptions, Vec<DataPoint>)| {
+                    let sink = sink.clone();
+                    async move {
+                        let timestamps: Vec<i64> = data.iter().map(DataPoint::timestamp).collect();
+                        sink.lock().unwrap().push((options.symbol, timestamps))
+                    }
+                },
+            ))
+            .with_clock(Arc::new(clock.clone()));
+        let mut websocket = WebSocket::new_with_session(client, mock_session(drain).await);
+        for options in [
+            ChartOptions::new("NASDAQ:AAPL", Interval::Daily),
+            ChartOptions::new("NASDAQ:MSFT", Interval::Daily).exclude_unclosed(true),
+        ] {
+            websocket.set_market(options).await.unwrap();
+        }
+
+        let now = clock
+            .system_time()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        let (closed, forming) = (now - 2 * 86_400, now - 60);
+        let bars = json!({ "s": [
+            { "i": 0, "v": [closed, 1.0, 1.0, 1.0, 1.0, 1.0] },
+            { "i": 1, "v": [forming, 1.0, 1.0, 1.0, 1.0, 1.0] },
+        ] });
+        let forming_only = json!({ "s": [{ "i": 1, "v": [forming, 1.0, 1.0, 1.0, 1.0, 1.0] }] });
+        for series in [
+            json!({ "sds_1": bars, "sds_2": bars }),
+            json!({ "sds_1": forming_only, "sds_2": forming_only }),
+        ] {
+            websocket
+                .client
+                .handle_events(
+                    TradingViewDataEvent::OnChartData,
+                    &vec![json!("cs_test"), series],
+                )
+                .await;
+        }
+
+        let mut received = received.lock().unwrap().clone();
+        received.sort();
+        assert_eq!(
+            received,
+            vec![
+                ("NASDAQ:AAPL".to_string(), vec![closed, forming]),
+                ("NASDAQ:AAPL".to_string(), vec![forming]),
+                ("NASDAQ:MSFT".to_string(), vec![closed]),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_exclude_unclosed_delivers_bar_after_rollover() {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let sink = received.clone();
+        let clock = MockClock::new();
+        let client = WebSocketClient::default()
+            .set_callbacks(Callbacks::default().on_chart_data(
+                move |(_, data): (ChartOptions, Vec<DataPoint>)| {
+                    let sink = sink.clone();
+                    async move {
+                        let bars: Vec<(i64, f64)> = data
+                            .iter()
+                            .map(|bar| (bar.timestamp(), bar.close()))
+                            .collect();
+                        sink.lock().unwrap().push(bars)
+                    }
+                },
+            ))
+            .with_clock(Arc::new(clock.clone()));
+        let mut websocket = WebSocket::new_with_session(client, mock_session(drain).await);
+        websocket
+            .set_market(ChartOptions::new("NASDAQ:MSFT", Interval::Daily).exclude_unclosed(true))
+            .await
+            .unwrap();
+
+        let now = clock
+            .system_time()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        let today = now - now % 86_400;
+        let bar =
+            |ts: i64, close: f64| json!({ "i": 0, "v": [ts, close, close, close, close, 1.0] });
+        let update = |bars: Vec<Value>| vec![json!("cs_test"), json!({ "sds_1": { "s": bars } })];
+
+        for (bars, advance) in [
+            (vec![bar(today - 86_400, 1.0), bar(today, 2.0)], 0),
+            (vec![bar(today, 3.0)], 0),
+            // the first update after the rollover only carries the new forming bar
+            (vec![bar(today + 86_400, 4.0)], 86_400 + 60),
+        ] {
+            clock.advance(Duration::from_secs(advance));
+            websocket
+                .client
+                .handle_events(TradingViewDataEvent::OnChartDataUpdate, &update(bars))
+                .await;
+        }
+
+        assert_eq!(
+            *received.lock().unwrap(),
+            vec![vec![(today - 86_400, 1.0)], vec![(today, 3.0)]]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_set_market_reuses_active_series() {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        let mut websocket = WebSocket::new_with_session(
+            WebSocketClient::default(),
+            mock_session(|s| record(s, tx)).await,
+        );
+        let options = ChartOptions::new("NASDAQ:AAPL", Interval::Daily);
+        websocket.set_market(options.clone()).await.unwrap();
+        websocket.set_market(options.clone()).await.unwrap();
+        assert_eq!(websocket.client.metadata.series.len(), 1);
+        assert_eq!(websocket.last_series_id().as_deref(), Some("sds_1"));
+
+        websocket
+            .set_market(options.clone().bar_count(100))
+            .await
+            .unwrap();
+        websocket.set_market(options.force_new(true)).await.unwrap();
+        assert_eq!(websocket.client.metadata.series.len(), 3);
+
+        websocket.socket.close().await.unwrap();
+        let sessions = rx
+            .await
+            .unwrap()
+            .iter()
+            .filter(|m| m.m == "chart_create_session")
+            .count();
+        assert_eq!(sessions, 3);
+    }
+
+    #[tokio::test]
+    async fn test_send_batch_preserves_order_in_one_frame() {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        let mut websocket = WebSocket::new_with_session(
+            WebSocketClient::default(),
+            mock_session(|s| record(s, tx)).await,
+        );
+        let symbols = ["BINANCE:BTCUSDT", "NASDAQ:AAPL", "NYSE:IBM"];
+        let batch: Vec<SocketMessageSer> = symbols
+            .iter()
+            .map(|symbol| SocketMessageSer::new("quote_add_symbols", payload!("qs_test", *symbol)))
+            .collect();
+        websocket.send_batch(&batch).await.unwrap();
+        assert_eq!(websocket.send_stats().sent, 1);
+
+        websocket.socket.close().await.unwrap();
+        let received: Vec<String> = rx
+            .await
+            .unwrap()
+            .iter()
+            .filter(|m| m.m == "quote_add_symbols")
+            .map(|m| m.p[1].as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(received, symbols);
+    }
+
+    #[tokio::test]
+    async fn test_set_market_sends_setup_in_one_frame() {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        let mut websocket = WebSocket::new_with_session(
+            WebSocketClient::default(),
+            mock_session(|s| record(s, tx)).await,
+        );
+        websocket
+            .set_market(ChartOptions::new("NASDAQ:AAPL", Interval::Daily))
+            .await
+            .unwrap();
+        assert_eq!(websocket.send_stats().sent, 1);
+
+        websocket.socket.close().await.unwrap();
+        let methods: Vec<String> = rx
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|m| m.m)
+            .filter(|m| m != "set_auth_token")
+            .collect();
+        assert_eq!(
+            methods,
+            ["chart_create_session", "resolve_symbol", "create_series"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_series_loading_progress() {
+        let progress = Arc::new(Mutex::new(Vec::new()));
+        let sink = progress.clone();
+        let client = WebSocketClient::default().set_callbacks(
+            Callbacks::default().on_series_loading(move |p: SeriesLoadingProgress| {
+                let sink = sink.clone();
+                async move { sink.lock().unwrap().push(p) }
+            }),
+        );
+        let mut websocket = WebSocket::new_with_session(client, mock_session(drain).await);
+        websocket
+            .set_market(ChartOptions::new("NASDAQ:AAPL", Interval::Daily).bar_count(5000))
+            .await
+            .unwrap();
+        let chart_session = websocket.client.metadata.series["sds_1"]
+            .chart_session
+            .clone();
+
+        for p in [
+            json!([chart_session, "sds_1", "s1"]),
+            json!([chart_session, "sds_9", "s1"]),
+        ] {
+            let message: SocketMessageDe =
+                serde_json::from_value(json!({ "m": "series_loading", "p": p })).unwrap();
+            websocket.handle_message_data(message).await.unwrap();
+        }
+
+        let progress = progress.lock().unwrap().clone();
+        assert_eq!(
+            progress,
+            vec![
+                SeriesLoadingProgress {
+                    series_id: "sds_1".to_string(),
+                    loaded: None,
+                    requested: Some(5000),
+                },
+                SeriesLoadingProgress {
+                    series_id: "sds_9".to_string(),
+                    loaded: None,
+                    requested: None,
+                },
+            ]
+        );
+    }
+
+    async fn symbol_error(reason: &str) -> Vec<Error> {
+        let (client, errors) = capture_errors();
+        let mut websocket = WebSocket::new_with_session(client, mock_session(drain).await);
+        websocket
+            .set_market(ChartOptions::new("NASDAQ:APPL", Interval::Daily))
+            .await
+            .unwrap();
+        let chart_session = websocket.client.metadata.series["sds_1"]
+            .chart_session
+            .clone();
+        let message: SocketMessageDe = serde_json::from_value(json!({
+            "m": "symbol_error",
+            "p": [chart_session, "sds_sym_1", reason]
+        }))
+        .unwrap();
+        websocket.handle_message_data(message).await.unwrap();
+        assert!(websocket.client.metadata.failed_series.contains("sds_1"));
+        let errors = std::mem::take(&mut *errors.lock().unwrap());
+        errors
+    }
+
+    #[tokio::test]
+    async fn test_symbol_error_not_found() {
+        let errors = symbol_error("invalid symbol").await;
+        assert!(matches!(
+            &errors[..],
+            [Error::SymbolNotFound { symbol }] if symbol == "NASDAQ:APPL"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_symbol_error_not_permitted() {
+        let errors = symbol_error("permission denied").await;
+        assert!(matches!(
+            &errors[..],
+            [Error::SymbolNotPermitted { symbol }] if symbol == "NASDAQ:APPL"
+        ));
+        assert!(symbol_error("group is closed").await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_series_limit_exceeded() {
+        let (mut client, errors) = capture_errors();
+        client
+            .handle_events(
+                TradingViewDataEvent::OnStudyError,
+                &vec![
+                    json!("cs_test"),
+                    json!("st4"),
+                    json!("sds_1"),
+                    json!("study_not_created: max number of studies per chart is 25"),
+                ],
+            )
+            .await;
+        client
+            .handle_events(
+                TradingViewDataEvent::OnError(TradingViewError::SeriesError),
+                &vec![
+                    json!("cs_test"),
+                    json!("sds_40"),
+                    json!("s40"),
+                    json!("series limit exceeded"),
+                ],
+            )
+            .await;
+        client
+            .handle_events(
+                TradingViewDataEvent::OnStudyError,
+                &vec![
+                    json!("cs_test"),
+                    json!("st5"),
+                    json!("sds_1"),
+                    json!("invalid input"),
+                ],
+            )
+            .await;
+
+        let errors = errors.lock().unwrap();
+        assert!(matches!(
+            &errors[..],
+            [
+                Error::SeriesLimitExceeded { limit: Some(25) },
+                Error::SeriesLimitExceeded { limit: None },
+                Error::StudyError { .. },
+            ]
+        ));
+        assert_eq!(
+            errors[0].to_string(),
+            "series limit exceeded, at most 25 per connection"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_series_chart_data_routed_per_series() {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let sink = |tag: &'static str| {
+            let sink = received.clone();
+            move |(options, _): (ChartOptions, Vec<DataPoint>)| {
+                let sink = sink.clone();
+                async move { sink.lock().unwrap().push((tag, options.symbol)) }
+            }
+        };
+        let client = WebSocketClient::default()
+            .set_callbacks(Callbacks::default().on_chart_data(sink("global")));
+        let mut websocket = WebSocket::new_with_session(client, mock_session(drain).await);
+        for symbol in ["NASDAQ:AAPL", "NASDAQ:MSFT", "NASDAQ:NVDA"] {
+            websocket
+                .set_market(ChartOptions::new(symbol, Interval::Daily))
+                .await
+                .unwrap();
+        }
+        assert_eq!(websocket.last_series_id().as_deref(), Some("sds_3"));
+        websocket
+            .on_series_chart_data("sds_1", sink("a"))
+            .on_series_chart_data("sds_2", sink("b"));
+
+        let bar = json!({ "s": [{ "i": 0, "v": [0.0, 1.0, 1.0, 1.0, 1.0, 1.0] }] });
+        for series_id in ["sds_1", "sds_2", "sds_3"] {
+            websocket
+                .client
+                .handle_events(
+                    TradingViewDataEvent::OnChartDataUpdate,
+                    &vec![json!("cs_test"), json!({ series_id: bar.clone() })],
+                )
+                .await;
+        }
+
+        let mut received = received.lock().unwrap().clone();
+        received.sort();
+        assert_eq!(
+            received,
+            vec![
+                ("a", "NASDAQ:AAPL".to_string()),
+                ("b", "NASDAQ:MSFT".to_string()),
+                ("global", "NASDAQ:NVDA".to_string()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_guest_rejects_seconds_interval() {
+        let socket = mock_session(drain).await;
+        let mut websocket = WebSocket::new_with_session(WebSocketClient::default(), socket);
+        assert!(websocket.is_guest());
+
+        let result = websocket
+            .set_market(ChartOptions::new("BINANCE:BTCUSDT", Interval::OneSecond))
+            .await;
+        assert!(matches!(result, Err(Error::GuestNotPermitted(_))));
+        assert_eq!(websocket.client.metadata.series_count, 0);
+    }
+
+    #[test]
+    fn test_builder_guest_token() {
+        let builder = WebSocket::guest();
+        assert_eq!(builder.auth_token.as_deref(), Some(GUEST_AUTH_TOKEN));
+    }
+
+    #[test]
+    fn test_builder_user_auth_token() {
+        let user = UserCookies {
+            auth_token: "user_auth_token".to_string(),
+            ..Default::default()
+        };
+        let builder = WebSocket::new().user(&user);
+        assert_eq!(builder.auth_token.as_deref(), Some("user_auth_token"));
+
+        let builder = WebSocket::new()
+            .auth_token("raw_token")
+            .user(&UserCookies::default());
+        assert_eq!(builder.auth_token.as_deref(), Some("raw_token"));
+    }
 }
@@ -0,0 +1,125 @@
+use std::{
+    fmt::Debug,
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
+};
+
+use futures_util::future::BoxFuture;
+use tokio::sync::watch;
+
+/// Source of the current time for time-dependent state such as cache expiry, quote coalescing
+/// or idle timeouts, so tests can move time forward instead of sleeping.
+pub trait Clock: Debug + Send + Sync {
+    fn now(&self) -> Instant;
+
+    /// Wall-clock time, e.g. to tell whether the latest bar is still forming.
+    fn system_time(&self) -> SystemTime;
+
+    /// Completes once `duration` has passed on this clock.
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()>;
+}
+
+/// The system clocks, used unless another clock is configured.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn system_time(&self) -> SystemTime {
+        SystemTime::now()
+    }
+
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        Box::pin(tokio::time::sleep(duration))
+    }
+}
+
+/// A clock that only moves when told to. Clones share the same time, and sleeps complete
+/// when [`advance`](Self::advance) moves the clock past their deadline.
+#[derive(Debug, Clone)]
+pub struct MockClock {
+    start: Instant,
+    start_system: SystemTime,
+    elapsed: Arc<watch::Sender<Duration>>,
+}
+
+impl Default for MockClock {
+    fn default() -> Self {
+        MockClock::new()
+    }
+}
+
+impl MockClock {
+    /// Starts at the current system time.
+    pub fn new() -> Self {
+        MockClock {
+            start: Instant::now(),
+            start_system: SystemTime::now(),
+            elapsed: Arc::new(watch::Sender::new(Duration::ZERO)),
+        }
+    }
+
+    pub fn advance(&self, by: Duration) {
+        self.elapsed.send_modify(|elapsed| *elapsed += by);
+    }
+
+    fn elapsed(&self) -> Duration {
+        *self.elapsed.borrow()
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        self.start + self.elapsed()
+    }
+
+    fn system_time(&self) -> SystemTime {
+        self.start_system + self.elapsed()
+    }
+
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        let deadline = self.elapsed() + duration;
+        let mut elapsed = self.elapsed.subscribe();
+        Box::pin(async move {
+            if elapsed.wait_for(|e| *e >= deadline).await.is_err() {
+                // every clone is gone, so time never moves again
+                std::future::pending::<()>().await;
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mock_clock_advances_shared_time() {
+        let clock = MockClock::new();
+        let (start, start_system) = (clock.now(), clock.system_time());
+        assert_eq!(clock.now(), start);
+
+        let shared = clock.clone();
+        shared.advance(Duration::from_secs(90));
+        assert_eq!(clock.now().duration_since(start), Duration::from_secs(90));
+        assert_eq!(
+            clock.system_time().duration_since(start_system).unwrap(),
+            Duration::from_secs(90)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_mock_clock_sleep_completes_on_advance() {
+        use futures_util::FutureExt;
+
+        let clock = MockClock::new();
+        let mut sleep = clock.sleep(Duration::from_secs(60));
+        clock.advance(Duration::from_secs(59));
+        assert!((&mut sleep).now_or_never().is_none());
+        clock.advance(Duration::from_secs(1));
+        assert!(sleep.now_or_never().is_some());
+    }
+}
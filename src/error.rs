@@ -1,11 +1,14 @@
 use thiserror::Error;
+use tokio_tungstenite::tungstenite::{error::ProtocolError, Error as WsError};
 
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("Generic {0}")]
     Generic(String),
     #[error("failed to send the api request")]
-    RequestError(#[from] reqwest::Error),
+    RequestError(#[source] reqwest::Error),
+    #[error("request timed out")]
+    Timeout(#[source] reqwest::Error),
     #[error("failed to parse the api response")]
     JsonParseError(#[from] serde_json::Error),
     #[error("failed to convert into int from {}", .0)]
@@ -16,8 +19,9 @@ pub enum Error {
     LoginError(#[from] LoginError),
     #[error("failed to capture regex data")]
     RegexError(#[from] regex::Error),
+    /// Boxed to keep `Result<_, Error>` small; `?` still converts a bare tungstenite error.
     #[error("can not establish websocket connection")]
-    WebSocketError(#[from] tokio_tungstenite::tungstenite::Error),
+    WebSocketError(#[from] Box<WsError>),
     #[error("no chart token found")]
     NoChartTokenFound,
     #[error("No scan data found")]
@@ -32,8 +36,32 @@ pub enum Error {
     SymbolsNotSpecified,
     #[error("no search data found")]
     NoSearchDataFound,
+    #[error("bar count, range or from/to not specified")]
+    ChartRangeNotSpecified,
+    #[error("both range and from/to are specified")]
+    ConflictingChartRange,
+    #[error("replay lookback must be positive")]
+    InvalidReplayLookback,
+    #[error("{} requires an authenticated session", .0)]
+    GuestNotPermitted(String),
+    #[error("unsupported interval {}", .0)]
+    UnsupportedInterval(String),
+    #[error("unsupported range {}", .0)]
+    UnsupportedRange(String),
+    #[error("unsupported language code {}", .0)]
+    UnsupportedLanguageCode(String),
+    #[error("unsupported country {}", .0)]
+    UnsupportedCountry(String),
     #[error("inexistent or unsupported indicator {}", .0)]
     IndicatorDataNotFound(String),
+    #[error("study {study_id} failed: {reason}")]
+    StudyError { study_id: String, reason: String },
+    #[error("symbol {symbol} not found")]
+    SymbolNotFound { symbol: String },
+    #[error("symbol {symbol} is not permitted for this account")]
+    SymbolNotPermitted { symbol: String },
+    #[error("series limit exceeded{}", .limit.map(|l| format!(", at most {l} per connection")).unwrap_or_default())]
+    SeriesLimitExceeded { limit: Option<u32> },
     #[error("tokio task join error")]
     TokioJoinError(#[from] tokio::task::JoinError),
     #[error("url parse error")]
@@ -44,8 +72,159 @@ pub enum Error {
     ZipError(#[from] zip::result::ZipError),
     #[error("io error")]
     IOError(#[from] std::io::Error),
-    #[error("TradingView error")]
-    TradingViewError(#[from] TradingViewError),
+    #[error("currency conversion failed: {}", .0)]
+    CurrencyConversion(String),
+    #[error("invalid csv at line {line}: {reason}")]
+    InvalidCsv { line: usize, reason: String },
+    #[cfg(feature = "arrow")]
+    #[error("arrow error")]
+    ArrowError(#[from] arrow_schema::ArrowError),
+    #[cfg(feature = "parquet")]
+    #[error("parquet error")]
+    ParquetError(#[from] parquet::errors::ParquetError),
+    #[error("TradingView error: {0}")]
+    TradingViewError(TradingViewError),
+    /// An error reported by TradingView, e.g. a quote with an `error` status. `message` is
+    /// the raw payload of the frame.
+    #[error("TradingView error: {message}")]
+    ServerError {
+        #[source]
+        code: TradingViewError,
+        message: String,
+    },
+}
+
+impl Error {
+    /// Classifies the reason of a `symbol_error` message, `None` for reasons that say
+    /// neither that the symbol does not exist nor that it is not permitted.
+    pub(crate) fn from_symbol_error(symbol: &str, reason: &str) -> Option<Error> {
+        let reason = reason.to_lowercase();
+        let symbol = symbol.to_string();
+        if ["invalid symbol", "no_such_symbol", "unknown symbol"]
+            .iter()
+            .any(|r| reason.contains(r))
+        {
+            Some(Error::SymbolNotFound { symbol })
+        } else if ["permission denied", "no_permission", "not permitted"]
+            .iter()
+            .any(|r| reason.contains(r))
+        {
+            Some(Error::SymbolNotPermitted { symbol })
+        } else {
+            None
+        }
+    }
+
+    /// A [`Error::ServerError`] carrying the raw payload of an error frame.
+    pub(crate) fn from_server(code: TradingViewError, payload: &[serde_json::Value]) -> Error {
+        Error::ServerError {
+            code,
+            message: serde_json::Value::from(payload).to_string(),
+        }
+    }
+
+    /// Recognizes a study or series error reason saying the connection holds too many series
+    /// or studies. The limit is the first number in the reason, when there is one.
+    pub(crate) fn from_limit_error(reason: &str) -> Option<Error> {
+        let lowercase = reason.to_lowercase();
+        if ![
+            "study_not_created",
+            "limit exceeded",
+            "too many",
+            "max number of",
+        ]
+        .iter()
+        .any(|r| lowercase.contains(r))
+        {
+            return None;
+        }
+        let limit = reason
+            .split(|c: char| !c.is_ascii_digit())
+            .find(|digits| !digits.is_empty())
+            .and_then(|digits| digits.parse().ok());
+        Some(Error::SeriesLimitExceeded { limit })
+    }
+}
+
+impl From<TradingViewError> for Error {
+    fn from(e: TradingViewError) -> Self {
+        Error::TradingViewError(e)
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_timeout() {
+            Error::Timeout(e)
+        } else {
+            Error::RequestError(e)
+        }
+    }
+}
+
+impl From<WsError> for Error {
+    fn from(e: WsError) -> Self {
+        Error::WebSocketError(Box::new(e))
+    }
+}
+
+/// Coarse classification of a websocket failure, derived from the typed tungstenite error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WebSocketErrorKind {
+    /// The connection was closed, normally or because it was already closed.
+    ConnectionClosed,
+    /// The peer reset or dropped the connection without a closing handshake.
+    ConnectionReset,
+    Timeout,
+    Protocol,
+    Other,
+}
+
+impl WebSocketErrorKind {
+    /// Whether reconnecting is likely to succeed.
+    pub fn is_recoverable(&self) -> bool {
+        matches!(
+            self,
+            WebSocketErrorKind::ConnectionClosed
+                | WebSocketErrorKind::ConnectionReset
+                | WebSocketErrorKind::Timeout
+        )
+    }
+}
+
+impl From<&WsError> for WebSocketErrorKind {
+    fn from(e: &WsError) -> Self {
+        match e {
+            WsError::ConnectionClosed | WsError::AlreadyClosed => {
+                WebSocketErrorKind::ConnectionClosed
+            }
+            WsError::Io(io) => match io.kind() {
+                std::io::ErrorKind::TimedOut | std::io::ErrorKind::WouldBlock => {
+                    WebSocketErrorKind::Timeout
+                }
+                std::io::ErrorKind::ConnectionReset
+                | std::io::ErrorKind::ConnectionAborted
+                | std::io::ErrorKind::BrokenPipe
+                | std::io::ErrorKind::UnexpectedEof => WebSocketErrorKind::ConnectionReset,
+                _ => WebSocketErrorKind::Other,
+            },
+            WsError::Protocol(ProtocolError::ResetWithoutClosingHandshake) => {
+                WebSocketErrorKind::ConnectionReset
+            }
+            WsError::Protocol(_) => WebSocketErrorKind::Protocol,
+            _ => WebSocketErrorKind::Other,
+        }
+    }
+}
+
+impl Error {
+    /// The kind of websocket failure, `None` for errors not raised by the websocket.
+    pub fn websocket_error_kind(&self) -> Option<WebSocketErrorKind> {
+        match self {
+            Error::WebSocketError(e) => Some(WebSocketErrorKind::from(e.as_ref())),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Error, PartialEq, Eq, Hash)]
@@ -80,6 +259,8 @@ pub enum LoginError {
     InvalidSession,
     #[error("Sessionid/signature is empty")]
     SessionNotFound,
+    #[error("missing cookies: {}", .0.join(", "))]
+    MissingCookies(Vec<String>),
     #[error("can not parse user id")]
     ParseIDError,
     #[error("can not parse username")]
@@ -91,3 +272,53 @@ pub enum LoginError {
     #[error("can not parse auth token")]
     ParseAuthTokenError,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_websocket_error_kind() {
+        let kind = |e: WsError| Error::from(e).websocket_error_kind();
+
+        let closed = kind(WsError::ConnectionClosed);
+        assert_eq!(closed, Some(WebSocketErrorKind::ConnectionClosed));
+        assert!(closed.unwrap().is_recoverable());
+
+        assert_eq!(
+            kind(WsError::Io(std::io::ErrorKind::ConnectionReset.into())),
+            Some(WebSocketErrorKind::ConnectionReset)
+        );
+        assert_eq!(
+            kind(WsError::Io(std::io::ErrorKind::TimedOut.into())),
+            Some(WebSocketErrorKind::Timeout)
+        );
+
+        let protocol = kind(WsError::Protocol(ProtocolError::WrongHttpMethod));
+        assert_eq!(protocol, Some(WebSocketErrorKind::Protocol));
+        assert!(!protocol.unwrap().is_recoverable());
+
+        assert_eq!(Error::NoChartTokenFound.websocket_error_kind(), None);
+    }
+
+    #[test]
+    fn test_tradingview_error_display_includes_code() {
+        let error = Error::from(TradingViewError::SymbolError);
+        assert_eq!(error.to_string(), "TradingView error: symbol_error");
+        assert!(std::error::Error::source(&error).is_none());
+
+        let payload = [
+            serde_json::json!("cs_1"),
+            serde_json::json!("sds_sym_1"),
+            serde_json::json!("unexpected reason"),
+        ];
+        let error = Error::from_server(TradingViewError::SymbolError, &payload);
+        assert_eq!(
+            error.to_string(),
+            r#"TradingView error: ["cs_1","sds_sym_1","unexpected reason"]"#
+        );
+        let source = std::error::Error::source(&error).unwrap();
+        assert_eq!(source.to_string(), "symbol_error");
+        assert!(std::error::Error::source(source).is_none());
+    }
+}
@@ -1,6 +1,7 @@
 pub mod callback;
 pub mod chart;
 pub mod client;
+pub mod clock;
 pub mod error;
 pub mod models;
 pub mod quote;
@@ -16,13 +17,34 @@ static UA: &str =
 pub use crate::client::misc::{
     advanced_search_symbol, get_builtin_indicators, get_chart_token, get_drawing,
     get_indicator_metadata, get_private_indicators, get_quote_token, list_symbols,
-    search_indicator,
+    search_indicator, SymbolSearch, SEARCH_PAGE_SIZE,
 };
+pub use crate::utils::DEFAULT_REQUEST_TIMEOUT;
 
 pub mod websocket {
     pub use crate::client::websocket::*;
 }
 
+/// Everything a typical streaming application needs: `use tradingview::prelude::*;`.
+pub mod prelude {
+    pub use crate::{
+        callback::Callbacks,
+        chart::{
+            models::{ChartHistoricalData, DataPoint, StudyResponseData, SymbolInfo, OHLCV},
+            ChartOptions, HistorySpec,
+        },
+        error::Error,
+        models::{
+            pine_indicator::ScriptType, CurrencyOverride, Interval, MarketAdjustment, Range,
+            SessionType,
+        },
+        quote::models::{QuoteData, QuoteValue},
+        socket::{DataServer, SocketConfig, TradingViewDataEvent},
+        websocket::{WebSocket, WebSocketBuilder, WebSocketClient},
+        Result,
+    };
+}
+
 pub use crate::models::*;
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -0,0 +1,100 @@
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::models::{FinancialPeriod, Symbol};
+
+/// Core financial statement figures of a symbol for one [`FinancialPeriod`].
+///
+/// Values are in the symbol's reporting currency. Fields TradingView does not publish for
+/// the period (balance sheet items for `TTM`, for example) are `None`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Fundamentals {
+    pub symbol: String,
+    pub period: FinancialPeriod,
+    pub total_revenue: Option<f64>,
+    pub gross_profit: Option<f64>,
+    pub net_income: Option<f64>,
+    pub ebitda: Option<f64>,
+    pub earnings_per_share_basic: Option<f64>,
+    pub earnings_per_share_diluted: Option<f64>,
+    pub total_assets: Option<f64>,
+    pub total_debt: Option<f64>,
+    pub free_cash_flow: Option<f64>,
+}
+
+/// A search result together with the classification and size TradingView's scanner reports
+/// for it. Fields the scanner does not publish for the symbol (sector of a forex pair, for
+/// example) are `None`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DetailedSymbol {
+    pub symbol: Symbol,
+    pub sector: Option<String>,
+    pub industry: Option<String>,
+    pub market_cap: Option<f64>,
+    pub currency: Option<String>,
+}
+
+impl DetailedSymbol {
+    pub(crate) const FIELDS: [&'static str; 4] =
+        ["sector", "industry", "market_cap_basic", "currency"];
+
+    pub(crate) fn from_scanner(symbol: Symbol, data: &Value) -> Self {
+        let text = |name: &str| {
+            data.get(name)
+                .and_then(Value::as_str)
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+        };
+        DetailedSymbol {
+            sector: text("sector"),
+            industry: text("industry"),
+            market_cap: data.get("market_cap_basic").and_then(Value::as_f64),
+            currency: text("currency").or_else(|| {
+                (!symbol.currency_code.is_empty()).then(|| symbol.currency_code.clone())
+            }),
+            symbol,
+        }
+    }
+}
+
+impl Fundamentals {
+    pub(crate) const FIELDS: [&'static str; 9] = [
+        "total_revenue",
+        "gross_profit",
+        "net_income",
+        "ebitda",
+        "earnings_per_share_basic",
+        "earnings_per_share_diluted",
+        "total_assets",
+        "total_debt",
+        "free_cash_flow",
+    ];
+
+    /// Scanner field names for `period`, e.g. `total_revenue_fq`.
+    pub(crate) fn scanner_fields(period: &FinancialPeriod) -> Vec<String> {
+        let suffix = period.to_string().to_lowercase();
+        Self::FIELDS
+            .iter()
+            .map(|field| format!("{field}_{suffix}"))
+            .collect()
+    }
+
+    /// Builds the figures from a scanner `symbol` response keyed by period-suffixed fields.
+    pub(crate) fn from_scanner(symbol: &str, period: FinancialPeriod, data: &Value) -> Self {
+        let suffix = period.to_string().to_lowercase();
+        let field = |name: &str| data.get(format!("{name}_{suffix}")).and_then(Value::as_f64);
+        Fundamentals {
+            symbol: symbol.to_string(),
+            total_revenue: field("total_revenue"),
+            gross_profit: field("gross_profit"),
+            net_income: field("net_income"),
+            ebitda: field("ebitda"),
+            earnings_per_share_basic: field("earnings_per_share_basic"),
+            earnings_per_share_diluted: field("earnings_per_share_diluted"),
+            total_assets: field("total_assets"),
+            total_debt: field("total_debt"),
+            free_cash_flow: field("free_cash_flow"),
+            period,
+        }
+    }
+}
@@ -1,13 +1,24 @@
+pub use self::fundamentals::*;
 pub use self::news::*;
+pub use self::screener::*;
 pub use self::MarketType::*;
 pub use crate::chart::models::*;
 pub use crate::quote::models::*;
 
-use std::{collections::HashMap, fmt::Display};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    str::FromStr,
+};
 
+use crate::{chart::HistorySpec, Error};
+use chrono::{DateTime, Datelike, Months, NaiveDate, NaiveTime, TimeZone};
+use iso_currency::Currency;
 use serde::{Deserialize, Deserializer, Serialize};
+pub mod fundamentals;
 pub mod news;
 pub mod pine_indicator;
+pub mod screener;
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ChartDrawing {
@@ -75,6 +86,30 @@ pub struct SymbolSearchResponse {
     pub symbols: Vec<Symbol>,
 }
 
+impl SymbolSearchResponse {
+    /// Collapses symbols sharing an exchange and ticker (see [`Symbol::id`]) into one entry at
+    /// the position of the first. The entry whose `exchange_source` is the listing exchange
+    /// itself is kept when there is one, otherwise the first. `remaining` is left as is.
+    pub fn dedup_by_id(&mut self) {
+        let mut kept: HashMap<SymbolId, usize> = HashMap::new();
+        let mut symbols: Vec<Symbol> = Vec::with_capacity(self.symbols.len());
+        for symbol in self.symbols.drain(..) {
+            match kept.get(&symbol.id()) {
+                Some(&i) => {
+                    if !symbols[i].is_primary_source() && symbol.is_primary_source() {
+                        symbols[i] = symbol;
+                    }
+                }
+                None => {
+                    kept.insert(symbol.id(), symbols.len());
+                    symbols.push(symbol);
+                }
+            }
+        }
+        self.symbols = symbols;
+    }
+}
+
 #[derive(Clone, PartialEq, Deserialize, Serialize, Debug, Default)]
 pub struct Symbol {
     pub symbol: String,
@@ -94,6 +129,48 @@ pub struct Symbol {
     pub type_specs: Vec<String>,
     #[serde(default, rename(deserialize = "source2"))]
     pub exchange_source: ExchangeSource,
+    #[serde(default)]
+    pub logoid: String,
+}
+
+/// Host serving instrument logos as `{LOGO_BASE_URL}/{logoid}.svg`.
+pub const LOGO_BASE_URL: &str = "https://s3-symbol-logo.tradingview.com";
+
+/// Identity of a listed instrument: its exchange and ticker.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SymbolId {
+    pub exchange: String,
+    pub symbol: String,
+}
+
+impl Symbol {
+    pub fn id(&self) -> SymbolId {
+        SymbolId {
+            exchange: self.exchange.clone(),
+            symbol: self.symbol.clone(),
+        }
+    }
+
+    fn is_primary_source(&self) -> bool {
+        self.exchange_source.id.eq_ignore_ascii_case(&self.exchange)
+    }
+
+    /// Compares only exchange and ticker, ignoring descriptive metadata.
+    pub fn identity_eq(&self, other: &Symbol) -> bool {
+        self.exchange == other.exchange && self.symbol == other.symbol
+    }
+
+    /// SVG logo at `https://s3-symbol-logo.tradingview.com/{logoid}.svg`, `None` for symbols
+    /// without a `logoid`. Ids may contain a path, e.g. `indices/s-and-p-500`.
+    pub fn logo_url(&self) -> Option<String> {
+        (!self.logoid.is_empty()).then(|| format!("{}/{}.svg", LOGO_BASE_URL, self.logoid))
+    }
+}
+
+/// Removes symbols sharing an exchange and ticker with an earlier entry, keeping the first.
+pub fn dedup_symbols(symbols: &mut Vec<Symbol>) {
+    let mut seen = HashSet::new();
+    symbols.retain(|symbol| seen.insert(symbol.id()));
 }
 
 #[derive(Clone, PartialEq, Deserialize, Serialize, Debug, Default)]
@@ -103,7 +180,7 @@ pub struct ExchangeSource {
     pub description: String,
 }
 
-#[derive(Debug, Default, Clone, Serialize)]
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
 pub enum SessionType {
     #[default]
     Regular,
@@ -123,11 +200,143 @@ impl Display for SessionType {
     }
 }
 
-#[derive(Debug, Clone, Default)]
+/// Period presets accepted as a chart `range`, counted back from the latest bar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Range {
+    OneDay,
+    FiveDays,
+    OneMonth,
+    ThreeMonths,
+    SixMonths,
+    YearToDate,
+    OneYear,
+    FiveYears,
+    All,
+}
+
+impl Range {
+    /// Every preset, from `OneDay` to `All`.
+    pub fn all() -> [Range; 9] {
+        [
+            Range::OneDay,
+            Range::FiveDays,
+            Range::OneMonth,
+            Range::ThreeMonths,
+            Range::SixMonths,
+            Range::YearToDate,
+            Range::OneYear,
+            Range::FiveYears,
+            Range::All,
+        ]
+    }
+
+    /// From midnight on January 1st to `now`, both in `now`'s timezone.
+    pub fn ytd<Tz: TimeZone>(now: &DateTime<Tz>) -> HistorySpec {
+        Self::since(now, now.year(), 1)
+    }
+
+    /// From midnight on the first day of `now`'s quarter to `now`.
+    pub fn qtd<Tz: TimeZone>(now: &DateTime<Tz>) -> HistorySpec {
+        Self::since(now, now.year(), (now.month() - 1) / 3 * 3 + 1)
+    }
+
+    /// From midnight on the first day of `now`'s month to `now`.
+    pub fn mtd<Tz: TimeZone>(now: &DateTime<Tz>) -> HistorySpec {
+        Self::since(now, now.year(), now.month())
+    }
+
+    fn since<Tz: TimeZone>(now: &DateTime<Tz>, year: i32, month: u32) -> HistorySpec {
+        let midnight = NaiveDate::from_ymd_opt(year, month, 1)
+            .unwrap_or_default()
+            .and_time(NaiveTime::MIN);
+        let tz = now.timezone();
+        // a DST gap can skip midnight, read it as UTC then
+        let start = tz
+            .from_local_datetime(&midnight)
+            .earliest()
+            .unwrap_or_else(|| tz.from_utc_datetime(&midnight));
+        HistorySpec::Between {
+            from: start.timestamp().max(0) as u64,
+            to: now.timestamp().max(0) as u64,
+        }
+    }
+}
+
+impl Display for Range {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Range::OneDay => write!(f, "1D"),
+            Range::FiveDays => write!(f, "5D"),
+            Range::OneMonth => write!(f, "1M"),
+            Range::ThreeMonths => write!(f, "3M"),
+            Range::SixMonths => write!(f, "6M"),
+            Range::YearToDate => write!(f, "YTD"),
+            Range::OneYear => write!(f, "12M"),
+            Range::FiveYears => write!(f, "60M"),
+            Range::All => write!(f, "ALL"),
+        }
+    }
+}
+
+impl FromStr for Range {
+    type Err = Error;
+
+    /// Parses the range token produced by `Display`, e.g. `"5D"`, `"YTD"` or `"60M"`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Range::all()
+            .into_iter()
+            .find(|range| range.to_string() == s)
+            .ok_or_else(|| Error::UnsupportedRange(s.to_string()))
+    }
+}
+
+/// Quote currency requested for a chart: an ISO 4217 currency, or any other code
+/// TradingView understands, such as the crypto quote assets `USDT` or `BTC`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CurrencyOverride {
+    Iso(Currency),
+    Code(String),
+}
+
+impl CurrencyOverride {
+    pub fn code(&self) -> &str {
+        match self {
+            CurrencyOverride::Iso(currency) => currency.code(),
+            CurrencyOverride::Code(code) => code,
+        }
+    }
+}
+
+impl From<Currency> for CurrencyOverride {
+    fn from(currency: Currency) -> Self {
+        CurrencyOverride::Iso(currency)
+    }
+}
+
+impl From<&str> for CurrencyOverride {
+    /// ISO codes map to [`CurrencyOverride::Iso`], anything else is kept as an upper-case code.
+    fn from(code: &str) -> Self {
+        let code = code.to_uppercase();
+        match Currency::from_code(&code) {
+            Some(currency) => CurrencyOverride::Iso(currency),
+            None => CurrencyOverride::Code(code),
+        }
+    }
+}
+
+impl Display for CurrencyOverride {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.code())
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub enum MarketAdjustment {
     #[default]
     Splits,
     Dividends,
+    /// Raw prices without split or dividend adjustment; no adjustment is sent to TradingView.
+    None,
 }
 
 impl Display for MarketAdjustment {
@@ -135,6 +344,7 @@ impl Display for MarketAdjustment {
         match self {
             MarketAdjustment::Splits => write!(f, "splits"),
             MarketAdjustment::Dividends => write!(f, "dividends"),
+            MarketAdjustment::None => write!(f, "none"),
         }
     }
 }
@@ -350,7 +560,7 @@ impl Display for Timezone {
     }
 }
 
-#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Hash)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Hash)]
 pub enum Interval {
     OneSecond = 0,
     FiveSeconds = 1,
@@ -403,6 +613,455 @@ impl Display for Interval {
     }
 }
 
+impl FromStr for Interval {
+    type Err = Error;
+
+    /// Parses the wire form produced by `Display`, e.g. `"1"`, `"4H"`, `"1D"` or `"12M"`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Interval::all()
+            .into_iter()
+            .find(|interval| interval.to_string() == s)
+            .ok_or_else(|| Error::UnsupportedInterval(s.to_string()))
+    }
+}
+
+impl TryFrom<u8> for Interval {
+    type Error = Error;
+
+    /// Inverse of [`Interval::code`].
+    fn try_from(code: u8) -> Result<Self, Self::Error> {
+        Interval::all()
+            .get(code as usize)
+            .copied()
+            .ok_or_else(|| Error::UnsupportedInterval(code.to_string()))
+    }
+}
+
+impl Ord for Interval {
+    /// Finest to coarsest, by [`Interval::seconds`].
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.seconds().cmp(&other.seconds())
+    }
+}
+
+impl PartialOrd for Interval {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Interval {
+    /// Parses the wire form accepted by `FromStr` as well as common aliases: TradingView
+    /// resolutions (`"60"`, `"240"`, `"D"`, `"W"`, `"M"`) and `<count><unit>` forms such as
+    /// `"1min"`, `"15m"`, `"4h"`, `"1day"`, `"1wk"`, `"3mo"` or `"1y"`. A lowercase `m` means
+    /// minutes and an uppercase `M` months. Anything not matching an interval is an error.
+    pub fn parse_flexible(s: &str) -> Result<Interval, Error> {
+        if let Ok(interval) = s.parse() {
+            return Ok(interval);
+        }
+        let unsupported = || Error::UnsupportedInterval(s.to_string());
+        let s = s.trim();
+        if s.is_empty() {
+            return Err(unsupported());
+        }
+        let digits = s.len() - s.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        let count: u64 = match &s[..digits] {
+            "" => 1,
+            n => n.parse().map_err(|_| unsupported())?,
+        };
+        let unit = s[digits..].trim();
+        const MINUTE: u64 = 60;
+        const DAY: u64 = 24 * 60 * MINUTE;
+        let months = match unit {
+            "M" => Some(count),
+            "m" => None,
+            _ => match unit.to_lowercase().as_str() {
+                "mo" | "mon" | "month" | "months" | "monthly" => Some(count),
+                "y" | "yr" | "year" | "years" | "yearly" => Some(count * 12),
+                _ => None,
+            },
+        };
+        if let Some(months) = months {
+            return match months {
+                1 => Ok(Interval::Monthly),
+                3 => Ok(Interval::Quarterly),
+                6 => Ok(Interval::SixMonths),
+                12 => Ok(Interval::Yearly),
+                _ => Err(unsupported()),
+            };
+        }
+        let unit_seconds = match unit.to_lowercase().as_str() {
+            "s" | "sec" | "secs" | "second" | "seconds" => 1,
+            "" | "m" | "min" | "mins" | "minute" | "minutes" => MINUTE,
+            "h" | "hr" | "hour" | "hours" | "hourly" => 60 * MINUTE,
+            "d" | "day" | "days" | "daily" => DAY,
+            "w" | "wk" | "week" | "weeks" | "weekly" => 7 * DAY,
+            _ => return Err(unsupported()),
+        };
+        Interval::all()
+            .into_iter()
+            .filter(|interval| interval.seconds() < 30 * DAY)
+            .find(|interval| interval.seconds() == count * unit_seconds)
+            .ok_or_else(unsupported)
+    }
+
+    /// Numeric resolution code, the enum discriminant (`OneSecond` = 0 through `Yearly` = 19).
+    pub fn code(self) -> u8 {
+        self as u8
+    }
+
+    /// Nominal length of one bar in seconds. Months count as 30 days and years as 365.
+    pub fn seconds(&self) -> u64 {
+        const MINUTE: u64 = 60;
+        const HOUR: u64 = 60 * MINUTE;
+        const DAY: u64 = 24 * HOUR;
+        match self {
+            Interval::OneSecond => 1,
+            Interval::FiveSeconds => 5,
+            Interval::TenSeconds => 10,
+            Interval::FifteenSeconds => 15,
+            Interval::ThirtySeconds => 30,
+            Interval::OneMinute => MINUTE,
+            Interval::ThreeMinutes => 3 * MINUTE,
+            Interval::FiveMinutes => 5 * MINUTE,
+            Interval::FifteenMinutes => 15 * MINUTE,
+            Interval::ThirtyMinutes => 30 * MINUTE,
+            Interval::FortyFiveMinutes => 45 * MINUTE,
+            Interval::OneHour => HOUR,
+            Interval::TwoHours => 2 * HOUR,
+            Interval::FourHours => 4 * HOUR,
+            Interval::Daily => DAY,
+            Interval::Weekly => 7 * DAY,
+            Interval::Monthly => 30 * DAY,
+            Interval::Quarterly => 90 * DAY,
+            Interval::SixMonths => 180 * DAY,
+            Interval::Yearly => 365 * DAY,
+        }
+    }
+
+    /// Open time of the bar following the one opening at `timestamp` (unix seconds), i.e.
+    /// when that bar closes. `Monthly` and coarser advance by calendar months in UTC, the
+    /// others by [`seconds`](Self::seconds).
+    pub fn next_boundary(&self, timestamp: i64) -> i64 {
+        let months = match self {
+            Interval::Monthly => 1,
+            Interval::Quarterly => 3,
+            Interval::SixMonths => 6,
+            Interval::Yearly => 12,
+            _ => return timestamp + self.seconds() as i64,
+        };
+        DateTime::from_timestamp(timestamp, 0)
+            .and_then(|open| open.checked_add_months(Months::new(months)))
+            .map_or(timestamp + self.seconds() as i64, |close| close.timestamp())
+    }
+
+    /// Every interval, from `OneSecond` to `Yearly`.
+    pub fn all() -> [Interval; 20] {
+        [
+            Interval::OneSecond,
+            Interval::FiveSeconds,
+            Interval::TenSeconds,
+            Interval::FifteenSeconds,
+            Interval::ThirtySeconds,
+            Interval::OneMinute,
+            Interval::ThreeMinutes,
+            Interval::FiveMinutes,
+            Interval::FifteenMinutes,
+            Interval::ThirtyMinutes,
+            Interval::FortyFiveMinutes,
+            Interval::OneHour,
+            Interval::TwoHours,
+            Interval::FourHours,
+            Interval::Daily,
+            Interval::Weekly,
+            Interval::Monthly,
+            Interval::Quarterly,
+            Interval::SixMonths,
+            Interval::Yearly,
+        ]
+    }
+
+    /// Returns `true` for sub-minute resolutions (`OneSecond` through `ThirtySeconds`).
+    pub fn is_seconds_based(&self) -> bool {
+        matches!(
+            self,
+            Interval::OneSecond
+                | Interval::FiveSeconds
+                | Interval::TenSeconds
+                | Interval::FifteenSeconds
+                | Interval::ThirtySeconds
+        )
+    }
+
+    /// Returns `true` for every resolution below one day, seconds-based ones included
+    /// (`OneSecond` through `FourHours`).
+    pub fn is_intraday(&self) -> bool {
+        !self.is_daily_or_higher()
+    }
+
+    /// Returns `true` for `Daily` and every coarser resolution (`Weekly` through `Yearly`).
+    pub fn is_daily_or_higher(&self) -> bool {
+        matches!(
+            self,
+            Interval::Daily
+                | Interval::Weekly
+                | Interval::Monthly
+                | Interval::Quarterly
+                | Interval::SixMonths
+                | Interval::Yearly
+        )
+    }
+}
+
+/// Markets TradingView lists symbols for, passed to [`list_symbols`](crate::list_symbols) and
+/// [`advanced_search_symbol`](crate::advanced_search_symbol) as `country` via `code()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Country {
+    UnitedStates,
+    Canada,
+    Mexico,
+    Brazil,
+    Argentina,
+    Chile,
+    Colombia,
+    Peru,
+    Venezuela,
+    UnitedKingdom,
+    Ireland,
+    Germany,
+    France,
+    Italy,
+    Spain,
+    Portugal,
+    Netherlands,
+    Belgium,
+    Luxembourg,
+    Switzerland,
+    Austria,
+    Sweden,
+    Norway,
+    Denmark,
+    Finland,
+    Iceland,
+    Poland,
+    CzechRepublic,
+    Hungary,
+    Romania,
+    Serbia,
+    Greece,
+    Cyprus,
+    Estonia,
+    Latvia,
+    Lithuania,
+    Slovakia,
+    Turkey,
+    Russia,
+    Israel,
+    SaudiArabia,
+    UnitedArabEmirates,
+    Qatar,
+    Kuwait,
+    Bahrain,
+    Egypt,
+    Morocco,
+    Tunisia,
+    Nigeria,
+    Kenya,
+    SouthAfrica,
+    India,
+    Pakistan,
+    Bangladesh,
+    SriLanka,
+    China,
+    HongKong,
+    Taiwan,
+    Japan,
+    SouthKorea,
+    Singapore,
+    Malaysia,
+    Thailand,
+    Indonesia,
+    Philippines,
+    Vietnam,
+    Australia,
+    NewZealand,
+}
+
+impl Country {
+    /// ISO 3166-1 alpha-2 code, the form TradingView expects.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Country::UnitedStates => "US",
+            Country::Canada => "CA",
+            Country::Mexico => "MX",
+            Country::Brazil => "BR",
+            Country::Argentina => "AR",
+            Country::Chile => "CL",
+            Country::Colombia => "CO",
+            Country::Peru => "PE",
+            Country::Venezuela => "VE",
+            Country::UnitedKingdom => "GB",
+            Country::Ireland => "IE",
+            Country::Germany => "DE",
+            Country::France => "FR",
+            Country::Italy => "IT",
+            Country::Spain => "ES",
+            Country::Portugal => "PT",
+            Country::Netherlands => "NL",
+            Country::Belgium => "BE",
+            Country::Luxembourg => "LU",
+            Country::Switzerland => "CH",
+            Country::Austria => "AT",
+            Country::Sweden => "SE",
+            Country::Norway => "NO",
+            Country::Denmark => "DK",
+            Country::Finland => "FI",
+            Country::Iceland => "IS",
+            Country::Poland => "PL",
+            Country::CzechRepublic => "CZ",
+            Country::Hungary => "HU",
+            Country::Romania => "RO",
+            Country::Serbia => "RS",
+            Country::Greece => "GR",
+            Country::Cyprus => "CY",
+            Country::Estonia => "EE",
+            Country::Latvia => "LV",
+            Country::Lithuania => "LT",
+            Country::Slovakia => "SK",
+            Country::Turkey => "TR",
+            Country::Russia => "RU",
+            Country::Israel => "IL",
+            Country::SaudiArabia => "SA",
+            Country::UnitedArabEmirates => "AE",
+            Country::Qatar => "QA",
+            Country::Kuwait => "KW",
+            Country::Bahrain => "BH",
+            Country::Egypt => "EG",
+            Country::Morocco => "MA",
+            Country::Tunisia => "TN",
+            Country::Nigeria => "NG",
+            Country::Kenya => "KE",
+            Country::SouthAfrica => "ZA",
+            Country::India => "IN",
+            Country::Pakistan => "PK",
+            Country::Bangladesh => "BD",
+            Country::SriLanka => "LK",
+            Country::China => "CN",
+            Country::HongKong => "HK",
+            Country::Taiwan => "TW",
+            Country::Japan => "JP",
+            Country::SouthKorea => "KR",
+            Country::Singapore => "SG",
+            Country::Malaysia => "MY",
+            Country::Thailand => "TH",
+            Country::Indonesia => "ID",
+            Country::Philippines => "PH",
+            Country::Vietnam => "VN",
+            Country::Australia => "AU",
+            Country::NewZealand => "NZ",
+        }
+    }
+}
+
+impl Country {
+    /// Every variant, grouped by region.
+    pub const ALL: [Country; 68] = [
+        Country::UnitedStates,
+        Country::Canada,
+        Country::Mexico,
+        Country::Brazil,
+        Country::Argentina,
+        Country::Chile,
+        Country::Colombia,
+        Country::Peru,
+        Country::Venezuela,
+        Country::UnitedKingdom,
+        Country::Ireland,
+        Country::Germany,
+        Country::France,
+        Country::Italy,
+        Country::Spain,
+        Country::Portugal,
+        Country::Netherlands,
+        Country::Belgium,
+        Country::Luxembourg,
+        Country::Switzerland,
+        Country::Austria,
+        Country::Sweden,
+        Country::Norway,
+        Country::Denmark,
+        Country::Finland,
+        Country::Iceland,
+        Country::Poland,
+        Country::CzechRepublic,
+        Country::Hungary,
+        Country::Romania,
+        Country::Serbia,
+        Country::Greece,
+        Country::Cyprus,
+        Country::Estonia,
+        Country::Latvia,
+        Country::Lithuania,
+        Country::Slovakia,
+        Country::Turkey,
+        Country::Russia,
+        Country::Israel,
+        Country::SaudiArabia,
+        Country::UnitedArabEmirates,
+        Country::Qatar,
+        Country::Kuwait,
+        Country::Bahrain,
+        Country::Egypt,
+        Country::Morocco,
+        Country::Tunisia,
+        Country::Nigeria,
+        Country::Kenya,
+        Country::SouthAfrica,
+        Country::India,
+        Country::Pakistan,
+        Country::Bangladesh,
+        Country::SriLanka,
+        Country::China,
+        Country::HongKong,
+        Country::Taiwan,
+        Country::Japan,
+        Country::SouthKorea,
+        Country::Singapore,
+        Country::Malaysia,
+        Country::Thailand,
+        Country::Indonesia,
+        Country::Philippines,
+        Country::Vietnam,
+        Country::Australia,
+        Country::NewZealand,
+    ];
+}
+
+impl Display for Country {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.code())
+    }
+}
+
+impl FromStr for Country {
+    type Err = Error;
+
+    /// Parses an alpha-2 code, case-insensitively.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Country::ALL
+            .into_iter()
+            .find(|country| country.code().eq_ignore_ascii_case(s))
+            .ok_or_else(|| Error::UnsupportedCountry(s.to_string()))
+    }
+}
+
+/// Every [`Country`], grouped by region, e.g. to fill a dropdown. This is [`Country::ALL`];
+/// it is not checked against TradingView, so listing a country may still return no symbols.
+pub fn supported_countries() -> Vec<Country> {
+    Country::ALL.to_vec()
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum LanguageCode {
     Arabic,
     Chinese,
@@ -410,6 +1069,7 @@ pub enum LanguageCode {
     Danish,
     Catalan,
     Dutch,
+    #[default]
     English,
     Estonian,
     French,
@@ -475,6 +1135,75 @@ impl Display for LanguageCode {
     }
 }
 
+impl FromStr for LanguageCode {
+    type Err = Error;
+
+    /// Parses TradingView codes (`ja`, `da_DK`) as well as POSIX locales
+    /// (`ja_JP.UTF-8`, `pt-BR`), matching on the language part.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let locale = s
+            .split(['.', '@'])
+            .next()
+            .unwrap_or_default()
+            .replace('-', "_");
+        let mut parts = locale.splitn(2, '_');
+        let language = parts.next().unwrap_or_default().to_lowercase();
+        let region = parts.next().unwrap_or_default().to_uppercase();
+
+        let code = match (language.as_str(), region.as_str()) {
+            ("zh", "TW" | "HK" | "MO") => LanguageCode::TraditionalChinese,
+            ("ar", _) => LanguageCode::Arabic,
+            ("zh", _) => LanguageCode::Chinese,
+            ("cs", _) => LanguageCode::Czech,
+            ("da", _) => LanguageCode::Danish,
+            ("ca", _) => LanguageCode::Catalan,
+            ("nl", _) => LanguageCode::Dutch,
+            ("en", _) => LanguageCode::English,
+            ("et", _) => LanguageCode::Estonian,
+            ("fr", _) => LanguageCode::French,
+            ("de", _) => LanguageCode::German,
+            ("el", _) => LanguageCode::Greek,
+            ("he" | "iw", _) => LanguageCode::Hebrew,
+            ("hu", _) => LanguageCode::Hungarian,
+            ("id", _) => LanguageCode::Indonesian,
+            ("it", _) => LanguageCode::Italian,
+            ("ja", _) => LanguageCode::Japanese,
+            ("ko", _) => LanguageCode::Korean,
+            ("fa", _) => LanguageCode::Persian,
+            ("pl", _) => LanguageCode::Polish,
+            ("pt", _) => LanguageCode::Portuguese,
+            ("ro", _) => LanguageCode::Romanian,
+            ("ru", _) => LanguageCode::Russian,
+            ("sk", _) => LanguageCode::Slovak,
+            ("es", _) => LanguageCode::Spanish,
+            ("sv", _) => LanguageCode::Swedish,
+            ("th", _) => LanguageCode::Thai,
+            ("tr", _) => LanguageCode::Turkish,
+            ("vi", _) => LanguageCode::Vietnamese,
+            ("no" | "nb" | "nn", _) => LanguageCode::Norwegian,
+            ("ms", _) => LanguageCode::Malay,
+            _ => return Err(Error::UnsupportedLanguageCode(s.to_string())),
+        };
+        Ok(code)
+    }
+}
+
+impl LanguageCode {
+    /// Detects the language from the `LC_ALL` and `LANG` environment variables,
+    /// falling back to English when neither maps to a supported language.
+    pub fn from_system() -> Self {
+        Self::from_locale_vars(|key| std::env::var(key).ok())
+    }
+
+    fn from_locale_vars(var: impl Fn(&str) -> Option<String>) -> Self {
+        ["LC_ALL", "LANG"]
+            .into_iter()
+            .filter_map(var)
+            .find_map(|locale| locale.parse().ok())
+            .unwrap_or_default()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize)]
 #[serde(untagged)]
 pub enum FinancialPeriod {
@@ -512,6 +1241,8 @@ impl Display for FinancialPeriod {
         }
     }
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum SymbolType {
     Stock,
     Index,
@@ -564,6 +1295,34 @@ impl Display for SymbolType {
     }
 }
 
+impl From<&str> for SymbolType {
+    fn from(s: &str) -> Self {
+        match s {
+            "stock" => SymbolType::Stock,
+            "index" => SymbolType::Index,
+            "forex" => SymbolType::Forex,
+            "futures" => SymbolType::Futures,
+            "bitcoin" => SymbolType::Bitcoin,
+            "crypto" => SymbolType::Crypto,
+            "expression" => SymbolType::Expression,
+            "spread" => SymbolType::Spread,
+            "cfd" => SymbolType::Cfd,
+            "economic" => SymbolType::Economic,
+            "equity" => SymbolType::Equity,
+            "dr" => SymbolType::Dr,
+            "bond" => SymbolType::Bond,
+            "right" => SymbolType::Right,
+            "warrant" => SymbolType::Warrant,
+            "fund" => SymbolType::Fund,
+            "structured" => SymbolType::Structured,
+            "commodity" => SymbolType::Commodity,
+            "fundamental" => SymbolType::Fundamental,
+            "spot" => SymbolType::Spot,
+            _ => SymbolType::Undefined,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, Default, Serialize, PartialEq)]
 pub enum MarketType {
     #[default]
@@ -822,3 +1581,290 @@ impl Display for EconomicCategory {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interval_grouping() {
+        assert!(Interval::OneMinute.is_intraday());
+        assert!(!Interval::OneMinute.is_seconds_based());
+        assert!(!Interval::OneMinute.is_daily_or_higher());
+
+        assert!(Interval::FiveSeconds.is_seconds_based());
+        assert!(Interval::FiveSeconds.is_intraday());
+
+        assert!(Interval::FourHours.is_intraday());
+        assert!(!Interval::FourHours.is_daily_or_higher());
+
+        assert!(Interval::Daily.is_daily_or_higher());
+        assert!(!Interval::Daily.is_intraday());
+        assert!(Interval::Yearly.is_daily_or_higher());
+    }
+
+    #[test]
+    fn test_dedup_symbols_by_identity() {
+        let symbol = |exchange: &str, description: &str| Symbol {
+            symbol: "AAPL".to_string(),
+            exchange: exchange.to_string(),
+            description: description.to_string(),
+            ..Default::default()
+        };
+        let mut symbols = vec![
+            symbol("NASDAQ", "Apple Inc."),
+            symbol("NASDAQ", "Apple Inc"),
+            symbol("BMV", "Apple Inc."),
+        ];
+        assert!(symbols[0].identity_eq(&symbols[1]));
+        assert_ne!(symbols[0], symbols[1]);
+
+        dedup_symbols(&mut symbols);
+        assert_eq!(symbols.len(), 2);
+        assert_eq!(symbols[0].description, "Apple Inc.");
+        assert_eq!(symbols[1].exchange, "BMV");
+    }
+
+    #[test]
+    fn test_search_response_dedup_by_id() {
+        let symbol = |ticker: &str, exchange: &str, source: &str| Symbol {
+            symbol: ticker.to_string(),
+            exchange: exchange.to_string(),
+            exchange_source: ExchangeSource {
+                id: source.to_string(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut response = SymbolSearchResponse {
+            remaining: 10,
+            symbols: vec![
+                symbol("SHEL", "NYSE", "CBOE"),
+                symbol("SHEL", "LSE", "LSE"),
+                symbol("SHEL", "NYSE", "NYSE"),
+                symbol("SHEL", "NYSE", "BATS"),
+            ],
+        };
+        response.dedup_by_id();
+
+        let kept: Vec<(&str, &str)> = response
+            .symbols
+            .iter()
+            .map(|s| (s.exchange.as_str(), s.exchange_source.id.as_str()))
+            .collect();
+        assert_eq!(kept, vec![("NYSE", "NYSE"), ("LSE", "LSE")]);
+        assert_eq!(response.remaining, 10);
+    }
+
+    #[test]
+    fn test_symbol_logo_url() {
+        let symbol: Symbol =
+            serde_json::from_str(r#"{"symbol":"AAPL","exchange":"NASDAQ","logoid":"apple"}"#)
+                .unwrap();
+        assert_eq!(
+            symbol.logo_url().as_deref(),
+            Some("https://s3-symbol-logo.tradingview.com/apple.svg")
+        );
+        let index = Symbol {
+            logoid: "indices/s-and-p-500".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            index.logo_url().as_deref(),
+            Some("https://s3-symbol-logo.tradingview.com/indices/s-and-p-500.svg")
+        );
+        assert_eq!(Symbol::default().logo_url(), None);
+    }
+
+    #[test]
+    fn test_language_code_parsing() {
+        assert_eq!(
+            "ja".parse::<LanguageCode>().unwrap(),
+            LanguageCode::Japanese
+        );
+        assert_eq!(
+            "zh_TW.UTF-8".parse::<LanguageCode>().unwrap(),
+            LanguageCode::TraditionalChinese
+        );
+        assert_eq!(
+            "pt-BR".parse::<LanguageCode>().unwrap(),
+            LanguageCode::Portuguese
+        );
+        assert!("C".parse::<LanguageCode>().is_err());
+    }
+
+    #[test]
+    fn test_language_code_from_locale_vars() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |key: &str| {
+                vars.iter()
+                    .find(|(k, _)| *k == key)
+                    .map(|(_, v)| v.to_string())
+            }
+        };
+        assert_eq!(
+            LanguageCode::from_locale_vars(env(&[("LANG", "de_DE.UTF-8")])),
+            LanguageCode::German
+        );
+        assert_eq!(
+            LanguageCode::from_locale_vars(env(&[("LC_ALL", "ko_KR.UTF-8"), ("LANG", "de_DE")])),
+            LanguageCode::Korean
+        );
+        assert_eq!(
+            LanguageCode::from_locale_vars(env(&[("LC_ALL", "C"), ("LANG", "POSIX")])),
+            LanguageCode::English
+        );
+    }
+
+    #[test]
+    fn test_currency_override_from_code() {
+        assert_eq!(
+            CurrencyOverride::from("eur"),
+            CurrencyOverride::Iso(Currency::EUR)
+        );
+        let usdt = CurrencyOverride::from("usdt");
+        assert_eq!(usdt, CurrencyOverride::Code("USDT".to_string()));
+        assert_eq!(usdt.to_string(), "USDT");
+    }
+
+    #[test]
+    fn test_interval_display_round_trip() {
+        for interval in Interval::all() {
+            assert_eq!(interval.to_string().parse::<Interval>().unwrap(), interval);
+        }
+        assert_eq!("1M".parse::<Interval>().unwrap(), Interval::Monthly);
+        assert!("30d".parse::<Interval>().is_err());
+    }
+
+    #[test]
+    fn test_interval_code_round_trip() {
+        assert_eq!(Interval::Daily.code(), 14);
+        for interval in Interval::all() {
+            assert_eq!(Interval::try_from(interval.code()).unwrap(), interval);
+        }
+        assert!(Interval::try_from(20).is_err());
+    }
+
+    #[test]
+    fn test_range_display_round_trip() {
+        for range in Range::all() {
+            assert_eq!(range.to_string().parse::<Range>().unwrap(), range);
+        }
+        assert_eq!(Range::FiveDays.to_string(), "5D");
+        assert!("5d".parse::<Range>().is_err());
+    }
+
+    #[test]
+    fn test_calendar_ranges() {
+        use chrono::FixedOffset;
+
+        let ict = FixedOffset::east_opt(7 * 3600).unwrap();
+        let at = |y, m, d, h| ict.with_ymd_and_hms(y, m, d, h, 0, 0).unwrap();
+        let between =
+            |from: DateTime<FixedOffset>, to: DateTime<FixedOffset>| HistorySpec::Between {
+                from: from.timestamp() as u64,
+                to: to.timestamp() as u64,
+            };
+
+        let now = at(2024, 5, 15, 10);
+        assert_eq!(Range::ytd(&now), between(at(2024, 1, 1, 0), now));
+        assert_eq!(Range::qtd(&now), between(at(2024, 4, 1, 0), now));
+        assert_eq!(Range::mtd(&now), between(at(2024, 5, 1, 0), now));
+
+        // still 2023 in UTC, already 2024 in the given zone
+        let new_year = at(2024, 1, 1, 3);
+        assert_eq!(
+            Range::ytd(&new_year),
+            HistorySpec::Between {
+                from: 1_704_042_000,
+                to: new_year.timestamp() as u64,
+            }
+        );
+        assert_eq!(
+            Range::ytd(&new_year.with_timezone(&chrono::Utc)),
+            HistorySpec::Between {
+                from: 1_672_531_200,
+                to: new_year.timestamp() as u64,
+            }
+        );
+    }
+
+    #[test]
+    fn test_supported_countries() {
+        let countries = supported_countries();
+        assert!(countries.contains(&Country::Vietnam));
+        assert!(countries.contains(&Country::UnitedStates));
+        assert_eq!("vn".parse::<Country>().unwrap(), Country::Vietnam);
+        assert_eq!(Country::UnitedStates.to_string(), "US");
+        for code in ["KP", "AQ", "XX", ""] {
+            assert!(code.parse::<Country>().is_err(), "{code}");
+        }
+        let codes: HashSet<&str> = countries.iter().map(Country::code).collect();
+        assert_eq!(codes.len(), countries.len());
+    }
+
+    #[test]
+    fn test_interval_next_boundary() {
+        // 2024-02-01T00:00:00Z
+        let feb = 1_706_745_600;
+        assert_eq!(Interval::OneMinute.next_boundary(feb), feb + 60);
+        assert_eq!(Interval::Daily.next_boundary(feb), feb + 86_400);
+        // 29 days in February 2024
+        assert_eq!(Interval::Monthly.next_boundary(feb), feb + 29 * 86_400);
+        // 2025-02-01T00:00:00Z
+        assert_eq!(Interval::Yearly.next_boundary(feb), 1_738_368_000);
+    }
+
+    #[test]
+    fn test_interval_parse_flexible() {
+        let parse = |s: &str| Interval::parse_flexible(s).unwrap();
+        assert_eq!(parse("60"), Interval::OneHour);
+        assert_eq!(parse("240"), Interval::FourHours);
+        assert_eq!(parse("D"), Interval::Daily);
+        assert_eq!(parse("W"), Interval::Weekly);
+        assert_eq!(parse("M"), Interval::Monthly);
+        assert_eq!(parse("1min"), Interval::OneMinute);
+        assert_eq!(parse("15m"), Interval::FifteenMinutes);
+        assert_eq!(parse("1h"), Interval::OneHour);
+        assert_eq!(parse("30s"), Interval::ThirtySeconds);
+        assert_eq!(parse("1day"), Interval::Daily);
+        assert_eq!(parse("3mo"), Interval::Quarterly);
+        assert_eq!(parse("1y"), Interval::Yearly);
+        for interval in Interval::all() {
+            assert_eq!(parse(&interval.to_string()), interval);
+        }
+        for invalid in ["", "7", "2d", "1x", "5M", "-1"] {
+            assert!(Interval::parse_flexible(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_interval_ordering() {
+        let mut intervals = vec![
+            Interval::Daily,
+            Interval::OneMinute,
+            Interval::Yearly,
+            Interval::ThirtySeconds,
+            Interval::FourHours,
+            Interval::OneSecond,
+            Interval::Weekly,
+            Interval::FortyFiveMinutes,
+        ];
+        intervals.sort();
+        assert_eq!(
+            intervals,
+            vec![
+                Interval::OneSecond,
+                Interval::ThirtySeconds,
+                Interval::OneMinute,
+                Interval::FortyFiveMinutes,
+                Interval::FourHours,
+                Interval::Daily,
+                Interval::Weekly,
+                Interval::Yearly,
+            ]
+        );
+        assert!(Interval::all().windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(Interval::all().into_iter().max(), Some(Interval::Yearly));
+    }
+}
@@ -11,6 +11,7 @@ pub enum NewsArea {
     Africa,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum NewsSection {
     AnalysisAll,
     AnalysisRecommendations,
@@ -30,6 +31,55 @@ pub struct NewsHeadlines {
     pub items: Vec<News>,
 }
 
+/// News category, paging and date window for
+/// [`list_news_filtered`](crate::client::news::list_news_filtered). Timestamps are unix
+/// seconds, like [`News::published`].
+///
+/// Only `section` is sent to the headlines endpoint. It takes no paging or date parameters,
+/// so `limit`, `before` and `after` are applied locally to the single batch it returns:
+/// a window older than that batch comes back empty.
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub struct NewsFilter {
+    /// News category, e.g. [`NewsSection::FinancialStatement`] for earnings reports.
+    pub section: Option<NewsSection>,
+    pub limit: Option<usize>,
+    /// Only items published strictly before this time.
+    pub before: Option<i64>,
+    /// Only items published at or after this time.
+    pub after: Option<i64>,
+}
+
+impl NewsFilter {
+    pub fn section(mut self, section: NewsSection) -> Self {
+        self.section = Some(section);
+        self
+    }
+
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn before(mut self, before: i64) -> Self {
+        self.before = Some(before);
+        self
+    }
+
+    pub fn after(mut self, after: i64) -> Self {
+        self.after = Some(after);
+        self
+    }
+}
+
+/// One page of headlines, newest first.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct NewsPage {
+    pub items: Vec<News>,
+    /// Matching items of the fetched batch left out by the `limit`. Older news the
+    /// endpoint did not return is not counted.
+    pub remaining: usize,
+}
+
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct News {
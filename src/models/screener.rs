@@ -0,0 +1,118 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Screener {
+    America,
+    Australia,
+    Canada,
+    Crypto,
+    Forex,
+    Germany,
+    Global,
+    India,
+    Japan,
+    UK,
+    Vietnam,
+}
+
+impl std::fmt::Display for Screener {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Screener::America => write!(f, "america"),
+            Screener::Australia => write!(f, "australia"),
+            Screener::Canada => write!(f, "canada"),
+            Screener::Crypto => write!(f, "crypto"),
+            Screener::Forex => write!(f, "forex"),
+            Screener::Germany => write!(f, "germany"),
+            Screener::Global => write!(f, "global"),
+            Screener::India => write!(f, "india"),
+            Screener::Japan => write!(f, "japan"),
+            Screener::UK => write!(f, "uk"),
+            Screener::Vietnam => write!(f, "vietnam"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ScreenerColumn {
+    Name,
+    Description,
+    Price,
+    Change,
+    Volume,
+    MarketCap,
+}
+
+impl std::fmt::Display for ScreenerColumn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScreenerColumn::Name => write!(f, "name"),
+            ScreenerColumn::Description => write!(f, "description"),
+            ScreenerColumn::Price => write!(f, "close"),
+            ScreenerColumn::Change => write!(f, "change"),
+            ScreenerColumn::Volume => write!(f, "volume"),
+            ScreenerColumn::MarketCap => write!(f, "market_cap_basic"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterOperation {
+    Greater,
+    Egreater,
+    Less,
+    Eless,
+    Equal,
+    #[serde(rename = "nequal")]
+    NotEqual,
+    InRange,
+    Match,
+}
+
+/// A single scanner condition, e.g. `change > 5`:
+/// `ScreenerFilter::new("change", FilterOperation::Greater, 5)`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ScreenerFilter {
+    pub left: String,
+    pub operation: FilterOperation,
+    pub right: Value,
+}
+
+impl ScreenerFilter {
+    pub fn new(left: &str, operation: FilterOperation, right: impl Into<Value>) -> Self {
+        Self {
+            left: left.to_string(),
+            operation,
+            right: right.into(),
+        }
+    }
+}
+
+/// One scanner result. Fields for columns that were not requested stay `None`.
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct ScreenerRow {
+    pub symbol: String,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub price: Option<f64>,
+    pub change: Option<f64>,
+    pub volume: Option<f64>,
+    pub market_cap: Option<f64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScreenerResponse {
+    #[serde(rename(deserialize = "totalCount"), default)]
+    pub total_count: u64,
+    pub data: Option<Vec<ScreenerResponseRow>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScreenerResponseRow {
+    #[serde(rename(deserialize = "s"))]
+    pub symbol: String,
+    #[serde(rename(deserialize = "d"))]
+    pub values: Vec<Value>,
+}
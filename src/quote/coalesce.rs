@@ -0,0 +1,109 @@
+use std::{
+    collections::{HashMap, HashSet},
+    time::{Duration, Instant},
+};
+
+/// Rate limits quote emissions to at most one per symbol per `interval`.
+///
+/// Ticks are always merged into the client's quote state; the coalescer only decides
+/// when a symbol's merged state is handed to `on_quote_data`. Symbols held back are
+/// remembered as pending and emitted once their interval has passed, so the final
+/// state is never dropped.
+#[derive(Debug, Clone)]
+pub struct QuoteCoalescer {
+    interval: Duration,
+    last_emit: HashMap<String, Instant>,
+    pending: HashSet<String>,
+}
+
+impl QuoteCoalescer {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            last_emit: HashMap::new(),
+            pending: HashSet::new(),
+        }
+    }
+
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Records a tick for `symbol` and returns the symbols whose merged quote should be
+    /// emitted now: `symbol` itself if its interval has passed, plus any pending symbols
+    /// that became due.
+    pub fn tick(&mut self, symbol: &str, now: Instant) -> Vec<String> {
+        self.pending.insert(symbol.to_string());
+        let due: Vec<String> = self
+            .pending
+            .iter()
+            .filter(|s| {
+                self.last_emit
+                    .get(*s)
+                    .is_none_or(|last| now.duration_since(*last) >= self.interval)
+            })
+            .cloned()
+            .collect();
+        for s in &due {
+            self.pending.remove(s);
+            self.last_emit.insert(s.clone(), now);
+        }
+        due
+    }
+
+    /// Takes every pending symbol regardless of its interval.
+    pub fn flush(&mut self, now: Instant) -> Vec<String> {
+        let pending: Vec<String> = self.pending.drain().collect();
+        for s in &pending {
+            self.last_emit.insert(s.clone(), now);
+        }
+        pending
+    }
+
+    pub fn has_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rapid_ticks_emit_once_per_interval() {
+        let start = Instant::now();
+        let mut coalescer = QuoteCoalescer::new(Duration::from_millis(100));
+
+        let emitted: usize = (0..50)
+            .map(|i| {
+                let now = start + Duration::from_millis(i * 10);
+                coalescer.tick("BINANCE:BTCUSDT", now).len()
+            })
+            .sum();
+
+        // ticks at 0, 100, 200, 300 and 400ms get through
+        assert_eq!(emitted, 5);
+        assert!(coalescer.has_pending());
+        assert_eq!(
+            coalescer.flush(start + Duration::from_millis(490)),
+            vec!["BINANCE:BTCUSDT".to_string()]
+        );
+        assert!(!coalescer.has_pending());
+    }
+
+    #[test]
+    fn test_pending_symbol_emitted_on_later_tick() {
+        let start = Instant::now();
+        let mut coalescer = QuoteCoalescer::new(Duration::from_millis(100));
+
+        assert_eq!(coalescer.tick("A", start), vec!["A".to_string()]);
+        assert!(coalescer
+            .tick("A", start + Duration::from_millis(50))
+            .is_empty());
+
+        let mut due = coalescer.tick("B", start + Duration::from_millis(120));
+        due.sort();
+        assert_eq!(due, vec!["A".to_string(), "B".to_string()]);
+        assert!(!coalescer.has_pending());
+    }
+}
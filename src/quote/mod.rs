@@ -1,3 +1,4 @@
+pub mod coalesce;
 pub mod models;
 pub(crate) mod utils;
 
@@ -53,3 +54,53 @@ lazy_static::lazy_static! {
         "provider_id"
     ];
 }
+
+/// Named subsets of [`ALL_QUOTE_FIELDS`] for sessions that need fewer fields.
+pub struct QuoteFields;
+
+impl QuoteFields {
+    /// Last price, its time and volume.
+    pub fn minimal() -> Vec<&'static str> {
+        vec!["lp", "lp_time", "volume"]
+    }
+
+    /// [`minimal`](Self::minimal) plus the best bid and ask with their sizes.
+    pub fn trading() -> Vec<&'static str> {
+        let mut fields = QuoteFields::minimal();
+        fields.extend(["bid", "ask", "bid_size", "ask_size"]);
+        fields
+    }
+
+    /// [`minimal`](Self::minimal) plus earnings per share, market cap and sector.
+    pub fn fundamentals() -> Vec<&'static str> {
+        let mut fields = QuoteFields::minimal();
+        fields.extend([
+            "basic_eps_net_income",
+            "earnings_per_share_basic_ttm",
+            "market_cap_basic",
+            "sector",
+        ]);
+        fields
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quote_field_presets_are_subsets() {
+        for preset in [
+            QuoteFields::minimal(),
+            QuoteFields::trading(),
+            QuoteFields::fundamentals(),
+        ] {
+            for field in &preset {
+                assert!(ALL_QUOTE_FIELDS.contains(field), "{field}");
+            }
+        }
+        assert!(QuoteFields::trading().contains(&"bid_size"));
+        assert!(QuoteFields::fundamentals().contains(&"market_cap_basic"));
+        assert_eq!(QuoteFields::minimal(), vec!["lp", "lp_time", "volume"]);
+    }
+}
@@ -1,4 +1,5 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
 
 #[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
 pub struct QuoteData {
@@ -15,43 +16,64 @@ pub struct QuoteData {
 #[cfg_attr(not(feature = "protobuf"), derive(Debug, Default))]
 pub struct QuoteValue {
     #[cfg_attr(feature = "protobuf", prost(double, optional, tag = "1"))]
-    #[serde(default)]
+    #[serde(default, deserialize_with = "finite_f64")]
     pub ask: Option<f64>,
     #[cfg_attr(feature = "protobuf", prost(double, optional, tag = "2"))]
-    #[serde(default)]
+    #[serde(default, deserialize_with = "finite_f64")]
     pub ask_size: Option<f64>,
     #[cfg_attr(feature = "protobuf", prost(double, optional, tag = "3"))]
-    #[serde(default)]
+    #[serde(default, deserialize_with = "finite_f64")]
     pub bid: Option<f64>,
     #[cfg_attr(feature = "protobuf", prost(double, optional, tag = "4"))]
-    #[serde(default)]
+    #[serde(default, deserialize_with = "finite_f64")]
     pub bid_size: Option<f64>,
     #[cfg_attr(feature = "protobuf", prost(double, optional, tag = "5"))]
-    #[serde(default, rename(deserialize = "ch"))]
+    #[serde(default, deserialize_with = "finite_f64", rename(deserialize = "ch"))]
     pub change: Option<f64>,
     #[cfg_attr(feature = "protobuf", prost(double, optional, tag = "6"))]
-    #[serde(default, rename(deserialize = "chp"))]
+    #[serde(default, deserialize_with = "finite_f64", rename(deserialize = "chp"))]
     pub change_percent: Option<f64>,
     #[cfg_attr(feature = "protobuf", prost(double, optional, tag = "7"))]
-    #[serde(default, rename(deserialize = "open_price"))]
+    #[serde(
+        default,
+        deserialize_with = "finite_f64",
+        rename(deserialize = "open_price")
+    )]
     pub open: Option<f64>,
     #[cfg_attr(feature = "protobuf", prost(double, optional, tag = "8"))]
-    #[serde(default, rename(deserialize = "high_price"))]
+    #[serde(
+        default,
+        deserialize_with = "finite_f64",
+        rename(deserialize = "high_price")
+    )]
     pub high: Option<f64>,
     #[cfg_attr(feature = "protobuf", prost(double, optional, tag = "9"))]
-    #[serde(default, rename(deserialize = "low_price"))]
+    #[serde(
+        default,
+        deserialize_with = "finite_f64",
+        rename(deserialize = "low_price")
+    )]
     pub low: Option<f64>,
     #[cfg_attr(feature = "protobuf", prost(double, optional, tag = "10"))]
-    #[serde(default, rename(deserialize = "prev_close_price"))]
+    #[serde(
+        default,
+        deserialize_with = "finite_f64",
+        rename(deserialize = "prev_close_price")
+    )]
     pub prev_close: Option<f64>,
     #[cfg_attr(feature = "protobuf", prost(double, optional, tag = "11"))]
-    #[serde(default, rename(deserialize = "lp"))]
+    #[serde(default, deserialize_with = "finite_f64", rename(deserialize = "lp"))]
     pub price: Option<f64>,
     #[cfg_attr(feature = "protobuf", prost(double, optional, tag = "12"))]
-    #[serde(default, rename(deserialize = "lp_time"))]
+    /// Time of the last trade (`lp_time`) in Unix seconds, like chart bar timestamps.
+    #[serde(
+        default,
+        deserialize_with = "finite_f64",
+        rename(deserialize = "lp_time")
+    )]
     pub timestamp: Option<f64>,
     #[cfg_attr(feature = "protobuf", prost(double, optional, tag = "13"))]
-    #[serde(default)]
+    #[serde(default, deserialize_with = "finite_f64")]
     pub volume: Option<f64>,
     #[cfg_attr(feature = "protobuf", prost(string, optional, tag = "14"))]
     #[serde(default, rename(deserialize = "currency_id"))]
@@ -66,3 +88,210 @@ pub struct QuoteValue {
     #[serde(default, rename(deserialize = "type"))]
     pub market_type: Option<String>,
 }
+
+/// Reads a quote number, mapping `null`, non-numeric values and non-finite numbers
+/// (including `"NaN"`/`"Infinity"` strings) to `None`.
+fn finite_f64<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = match Value::deserialize(deserializer)? {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.parse::<f64>().ok(),
+        _ => None,
+    };
+    Ok(value.filter(|v| v.is_finite()))
+}
+
+/// Old and new value of a single quote field that changed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuoteChange<T> {
+    pub old: Option<T>,
+    pub new: Option<T>,
+}
+
+/// Per-field changes between two [`QuoteValue`]s, `None` where the field is unchanged.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct QuoteDiff {
+    pub ask: Option<QuoteChange<f64>>,
+    pub ask_size: Option<QuoteChange<f64>>,
+    pub bid: Option<QuoteChange<f64>>,
+    pub bid_size: Option<QuoteChange<f64>>,
+    pub change: Option<QuoteChange<f64>>,
+    pub change_percent: Option<QuoteChange<f64>>,
+    pub open: Option<QuoteChange<f64>>,
+    pub high: Option<QuoteChange<f64>>,
+    pub low: Option<QuoteChange<f64>>,
+    pub prev_close: Option<QuoteChange<f64>>,
+    pub price: Option<QuoteChange<f64>>,
+    pub timestamp: Option<QuoteChange<f64>>,
+    pub volume: Option<QuoteChange<f64>>,
+    pub currency: Option<QuoteChange<String>>,
+    pub symbol: Option<QuoteChange<String>>,
+    pub exchange: Option<QuoteChange<String>>,
+    pub market_type: Option<QuoteChange<String>>,
+}
+
+fn field_change<T: PartialEq + Clone>(old: &Option<T>, new: &Option<T>) -> Option<QuoteChange<T>> {
+    if old == new {
+        return None;
+    }
+    Some(QuoteChange {
+        old: old.clone(),
+        new: new.clone(),
+    })
+}
+
+impl QuoteValue {
+    /// Compares `self` (the previous quote) with `other` (the current one).
+    ///
+    /// A field going from `None` to `Some` (or back) counts as a change.
+    pub fn diff(&self, other: &QuoteValue) -> QuoteDiff {
+        QuoteDiff {
+            ask: field_change(&self.ask, &other.ask),
+            ask_size: field_change(&self.ask_size, &other.ask_size),
+            bid: field_change(&self.bid, &other.bid),
+            bid_size: field_change(&self.bid_size, &other.bid_size),
+            change: field_change(&self.change, &other.change),
+            change_percent: field_change(&self.change_percent, &other.change_percent),
+            open: field_change(&self.open, &other.open),
+            high: field_change(&self.high, &other.high),
+            low: field_change(&self.low, &other.low),
+            prev_close: field_change(&self.prev_close, &other.prev_close),
+            price: field_change(&self.price, &other.price),
+            timestamp: field_change(&self.timestamp, &other.timestamp),
+            volume: field_change(&self.volume, &other.volume),
+            currency: field_change(&self.currency, &other.currency),
+            symbol: field_change(&self.symbol, &other.symbol),
+            exchange: field_change(&self.exchange, &other.exchange),
+            market_type: field_change(&self.market_type, &other.market_type),
+        }
+    }
+
+    /// `ask - bid`, `None` unless both sides are quoted. Zero for a locked market and
+    /// negative for a crossed one; the value is returned as is, not clamped.
+    pub fn spread(&self) -> Option<f64> {
+        Some(self.ask? - self.bid?)
+    }
+
+    /// `(ask + bid) / 2`, `None` unless both sides are quoted. Computed the same way for
+    /// crossed markets, so check [`spread`](Self::spread) when that matters.
+    pub fn mid(&self) -> Option<f64> {
+        Some((self.ask? + self.bid?) / 2.0)
+    }
+}
+
+impl QuoteDiff {
+    pub fn is_empty(&self) -> bool {
+        self.changed_fields().is_empty()
+    }
+
+    /// Names of the changed fields, in declaration order.
+    pub fn changed_fields(&self) -> Vec<&'static str> {
+        let flags = [
+            ("ask", self.ask.is_some()),
+            ("ask_size", self.ask_size.is_some()),
+            ("bid", self.bid.is_some()),
+            ("bid_size", self.bid_size.is_some()),
+            ("change", self.change.is_some()),
+            ("change_percent", self.change_percent.is_some()),
+            ("open", self.open.is_some()),
+            ("high", self.high.is_some()),
+            ("low", self.low.is_some()),
+            ("prev_close", self.prev_close.is_some()),
+            ("price", self.price.is_some()),
+            ("timestamp", self.timestamp.is_some()),
+            ("volume", self.volume.is_some()),
+            ("currency", self.currency.is_some()),
+            ("symbol", self.symbol.is_some()),
+            ("exchange", self.exchange.is_some()),
+            ("market_type", self.market_type.is_some()),
+        ];
+        flags
+            .into_iter()
+            .filter_map(|(name, changed)| changed.then_some(name))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quote_diff_reports_changed_fields() {
+        let old = QuoteValue {
+            price: Some(100.0),
+            volume: Some(1_000.0),
+            ..Default::default()
+        };
+        let new = QuoteValue {
+            price: Some(101.5),
+            volume: Some(1_000.0),
+            currency: Some("USD".to_string()),
+            ..Default::default()
+        };
+
+        let diff = old.diff(&new);
+        assert_eq!(
+            diff.price,
+            Some(QuoteChange {
+                old: Some(100.0),
+                new: Some(101.5)
+            })
+        );
+        assert_eq!(diff.volume, None);
+        assert_eq!(
+            diff.currency,
+            Some(QuoteChange {
+                old: None,
+                new: Some("USD".to_string())
+            })
+        );
+        assert_eq!(diff.changed_fields(), vec!["price", "currency"]);
+        assert!(old.diff(&old).is_empty());
+    }
+
+    #[test]
+    fn test_quote_value_drops_non_finite_numbers() {
+        let quote: QuoteValue = serde_json::from_value(serde_json::json!({
+            "lp": "NaN",
+            "ch": "Infinity",
+            "bid": null,
+            "ask": 101.5,
+            "volume": "1200"
+        }))
+        .unwrap();
+        assert_eq!(quote.price, None);
+        assert_eq!(quote.change, None);
+        assert_eq!(quote.bid, None);
+        assert_eq!(quote.ask, Some(101.5));
+        assert_eq!(quote.volume, Some(1200.0));
+    }
+
+    #[test]
+    fn test_quote_spread_and_mid() {
+        let quote = |bid: Option<f64>, ask: Option<f64>| QuoteValue {
+            bid,
+            ask,
+            ..Default::default()
+        };
+
+        let two_sided = quote(Some(99.5), Some(100.5));
+        assert_eq!(two_sided.spread(), Some(1.0));
+        assert_eq!(two_sided.mid(), Some(100.0));
+
+        for one_sided in [
+            quote(Some(99.5), None),
+            quote(None, Some(100.5)),
+            quote(None, None),
+        ] {
+            assert_eq!(one_sided.spread(), None);
+            assert_eq!(one_sided.mid(), None);
+        }
+
+        let crossed = quote(Some(101.0), Some(100.0));
+        assert_eq!(crossed.spread(), Some(-1.0));
+        assert_eq!(crossed.mid(), Some(100.5));
+    }
+}
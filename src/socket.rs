@@ -2,7 +2,7 @@ use crate::{
     error::Error,
     error::TradingViewError,
     payload,
-    utils::{format_packet, parse_packet},
+    utils::{format_batch_packet, format_packet, parse_packet},
     Result, UA,
 };
 use futures_util::{
@@ -11,12 +11,21 @@ use futures_util::{
 };
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::sync::Arc;
+use socket2::{SockRef, TcpKeepalive};
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, OnceLock, RwLock,
+    },
+    time::{Duration, Instant},
+};
 use tokio::{net::TcpStream, sync::Mutex};
 use tokio_tungstenite::{
-    connect_async,
+    client_async_tls, connect_async,
     tungstenite::{
         client::IntoClientRequest,
+        handshake::client::Request,
         http::{HeaderMap, HeaderValue},
         protocol::Message,
     },
@@ -50,6 +59,7 @@ pub enum TradingViewDataEvent {
     OnReplayDataEnd,
     OnStudyLoading,
     OnStudyCompleted,
+    OnStudyError,
     OnError(TradingViewError),
     UnknownEvent(String),
 }
@@ -74,13 +84,13 @@ impl From<String> for TradingViewDataEvent {
             "replay_resolutions" => TradingViewDataEvent::OnReplayResolutions,
             "replay_data_end" => TradingViewDataEvent::OnReplayDataEnd,
 
-            "study_loading" => TradingViewDataEvent::OnSeriesLoading,
+            "study_loading" => TradingViewDataEvent::OnStudyLoading,
             "study_completed" => TradingViewDataEvent::OnStudyCompleted,
 
             "symbol_error" => TradingViewDataEvent::OnError(TradingViewError::SymbolError),
             "series_error" => TradingViewDataEvent::OnError(TradingViewError::SeriesError),
             "critical_error" => TradingViewDataEvent::OnError(TradingViewError::CriticalError),
-            "study_error" => TradingViewDataEvent::OnError(TradingViewError::StudyError),
+            "study_error" => TradingViewDataEvent::OnStudyError,
             "protocol_error" => TradingViewDataEvent::OnError(TradingViewError::ProtocolError),
             "replay_error" => TradingViewDataEvent::OnError(TradingViewError::ReplayError),
 
@@ -144,7 +154,9 @@ pub enum SocketMessage<T> {
     Unknown(String),
 }
 
-#[derive(Default, Clone)]
+static FASTEST_DATA_SERVER: OnceLock<DataServer> = OnceLock::new();
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum DataServer {
     #[default]
     Data,
@@ -164,12 +176,133 @@ impl std::fmt::Display for DataServer {
     }
 }
 
+impl DataServer {
+    const PROBE_CANDIDATES: [DataServer; 3] = [
+        DataServer::Data,
+        DataServer::ProData,
+        DataServer::WidgetData,
+    ];
+    const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// Returns the data server with the lowest connect latency from this host.
+    ///
+    /// Each candidate is probed once with a connect-and-close, the winner is cached for the
+    /// lifetime of the process. Falls back to `DataServer::Data` if no server is reachable.
+    pub async fn fastest() -> DataServer {
+        if let Some(server) = FASTEST_DATA_SERVER.get() {
+            return *server;
+        }
+        let server = Self::select_fastest(&Self::PROBE_CANDIDATES, Self::probe)
+            .await
+            .unwrap_or_default();
+        debug!("selected data server: {}", server);
+        *FASTEST_DATA_SERVER.get_or_init(|| server)
+    }
+
+    /// Picks the candidate whose `probe` reports the lowest latency, ignoring failed probes.
+    pub(crate) async fn select_fastest<F, Fut>(
+        candidates: &[DataServer],
+        probe: F,
+    ) -> Option<DataServer>
+    where
+        F: Fn(DataServer) -> Fut,
+        Fut: Future<Output = Result<Duration>>,
+    {
+        let latencies =
+            futures_util::future::join_all(candidates.iter().map(|server| probe(*server))).await;
+        candidates
+            .iter()
+            .zip(latencies)
+            .filter_map(|(server, latency)| match latency {
+                Ok(latency) => Some((*server, latency)),
+                Err(e) => {
+                    debug!("data server {} is unreachable: {}", server, e);
+                    None
+                }
+            })
+            .min_by_key(|(_, latency)| *latency)
+            .map(|(server, _)| server)
+    }
+
+    async fn probe(server: DataServer) -> Result<Duration> {
+        let request = SocketSession::request(&SocketSession::url(&server)?)?;
+        let start = Instant::now();
+        let (mut socket, _response) =
+            tokio::time::timeout(Self::PROBE_TIMEOUT, connect_async(request))
+                .await
+                .map_err(|_| Error::Generic(format!("probing {server} timed out")))??;
+        let latency = start.elapsed();
+        socket.close(None).await.ok();
+        Ok(latency)
+    }
+}
+
+/// TCP level tuning applied to the connection before the websocket handshake.
+///
+/// The defaults leave the operating system settings untouched.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SocketConfig {
+    /// Disables Nagle's algorithm so small frames are sent immediately.
+    pub tcp_nodelay: bool,
+    /// Enables TCP keepalive probes after this much idle time, repeated at the same interval.
+    pub keepalive: Option<Duration>,
+}
+
+impl SocketConfig {
+    pub(crate) fn apply(&self, stream: &TcpStream) -> Result<()> {
+        if self.tcp_nodelay {
+            stream.set_nodelay(true)?;
+        }
+        if let Some(interval) = self.keepalive {
+            let keepalive = TcpKeepalive::new()
+                .with_time(interval)
+                .with_interval(interval);
+            SockRef::from(stream).set_tcp_keepalive(&keepalive)?;
+        }
+        Ok(())
+    }
+}
+
+/// Outcome counts of the messages a [`SocketSession`] sent, see [`SocketSession::send_stats`].
+#[derive(Debug, Clone, Default)]
+pub struct SendStats {
+    pub sent: u64,
+    pub failed: u64,
+    /// When the latest failed send happened and its error message.
+    pub last_error: Option<(Instant, String)>,
+}
+
+impl SendStats {
+    /// Share of sends that failed, `0.0` before anything was sent.
+    pub fn failure_rate(&self) -> f64 {
+        let total = self.sent + self.failed;
+        if total == 0 {
+            0.0
+        } else {
+            self.failed as f64 / total as f64
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct SendMetrics {
+    sent: AtomicU64,
+    failed: AtomicU64,
+    last_error: RwLock<Option<(Instant, String)>>,
+}
+
+/// Token sent by sessions without a logged in user, see [`SocketSession::is_guest`].
+pub const GUEST_AUTH_TOKEN: &str = "unauthorized_user_token";
+
 #[derive(Clone)]
 pub struct SocketSession {
     server: Arc<DataServer>,
+    url: Arc<Url>,
     auth_token: Arc<String>,
+    config: SocketConfig,
     read: Arc<Mutex<SplitStream<WebSocketStream<MaybeTlsStream<TcpStream>>>>>,
     write: Arc<Mutex<SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>, Message>>>,
+    metrics: Arc<SendMetrics>,
 }
 
 impl SocketSession {
@@ -197,23 +330,19 @@ impl SocketSession {
     /// An authentication message is sent using the write part of the connection.
     /// Finally, it returns the write and read parts of the connection.
     async fn connect(
-        server: &DataServer,
+        url: &Url,
         auth_token: &str,
+        config: &SocketConfig,
     ) -> Result<(
         SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>, Message>,
         SplitStream<WebSocketStream<MaybeTlsStream<TcpStream>>>,
     )> {
-        let url = Url::parse(&format!(
-            "wss://{}.tradingview.com/socket.io/websocket",
-            server
-        ))?;
-
-        let mut request = url.into_client_request()?;
-        request
-            .headers_mut()
-            .extend(WEBSOCKET_HEADERS.clone().into_iter());
-
-        let (socket, _response) = connect_async(request).await?;
+        let request = SocketSession::request(url)?;
+        let host = url.host_str().unwrap_or_default();
+        let port = url.port_or_known_default().unwrap_or(443);
+        let stream = TcpStream::connect((host, port)).await?;
+        config.apply(&stream)?;
+        let (socket, _response) = client_async_tls(request, stream).await?;
 
         let (mut write, read) = socket.split();
 
@@ -224,10 +353,34 @@ impl SocketSession {
         Ok((write, read))
     }
 
+    fn url(server: &DataServer) -> Result<Url> {
+        let url = Url::parse(&format!(
+            "wss://{}.tradingview.com/socket.io/websocket",
+            server
+        ))?;
+        Ok(url)
+    }
+
+    fn request(url: &Url) -> Result<Request> {
+        let mut request = url.as_str().into_client_request()?;
+        request.headers_mut().extend(WEBSOCKET_HEADERS.clone());
+        Ok(request)
+    }
+
+    pub fn server(&self) -> DataServer {
+        *self.server
+    }
+
+    /// Whether the session is authenticated with [`GUEST_AUTH_TOKEN`] rather than a user token.
+    pub fn is_guest(&self) -> bool {
+        self.auth_token.as_str() == GUEST_AUTH_TOKEN
+    }
+
     pub async fn reconnect(&mut self) -> Result<()> {
         let (write, read) = SocketSession::connect(
-            self.server.clone().as_ref(),
+            self.url.clone().as_ref(),
             self.auth_token.clone().as_ref(),
+            &self.config,
         )
         .await?;
         self.write = Arc::from(Mutex::new(write));
@@ -242,32 +395,88 @@ impl SocketSession {
     }
 
     pub async fn new(server: DataServer, auth_token: String) -> Result<SocketSession> {
-        let (write_stream, read_stream) = SocketSession::connect(&server, &auth_token).await?;
+        SocketSession::new_with_config(server, auth_token, SocketConfig::default()).await
+    }
 
-        let write = Arc::from(Mutex::new(write_stream));
-        let read = Arc::from(Mutex::new(read_stream));
-        let server = Arc::new(server);
-        let auth_token = Arc::new(auth_token);
+    pub async fn new_with_config(
+        server: DataServer,
+        auth_token: String,
+        config: SocketConfig,
+    ) -> Result<SocketSession> {
+        let url = SocketSession::url(&server)?;
+        SocketSession::open(server, url, auth_token, config).await
+    }
+
+    /// Connects to `url`, which is also used by [`SocketSession::reconnect`].
+    pub(crate) async fn open(
+        server: DataServer,
+        url: Url,
+        auth_token: String,
+        config: SocketConfig,
+    ) -> Result<SocketSession> {
+        let (write_stream, read_stream) =
+            SocketSession::connect(&url, &auth_token, &config).await?;
 
         Ok(SocketSession {
-            server,
-            auth_token,
-            write,
-            read,
+            server: Arc::new(server),
+            url: Arc::new(url),
+            auth_token: Arc::new(auth_token),
+            config,
+            write: Arc::from(Mutex::new(write_stream)),
+            read: Arc::from(Mutex::new(read_stream)),
+            metrics: Arc::default(),
         })
     }
 
+    /// Counts of successful and failed sends over the session's lifetime, reconnects
+    /// included, with the latest failure.
+    pub fn send_stats(&self) -> SendStats {
+        SendStats {
+            sent: self.metrics.sent.load(Ordering::Relaxed),
+            failed: self.metrics.failed.load(Ordering::Relaxed),
+            last_error: self
+                .metrics
+                .last_error
+                .read()
+                .unwrap_or_else(|e| e.into_inner())
+                .clone(),
+        }
+    }
+
+    /// Writes `message` and records the outcome in [`send_stats`](Self::send_stats).
+    async fn send_message(&self, message: Message) -> Result<()> {
+        match self.write.lock().await.send(message).await {
+            Ok(()) => {
+                self.metrics.sent.fetch_add(1, Ordering::Relaxed);
+                Ok(())
+            }
+            Err(e) => {
+                self.metrics.failed.fetch_add(1, Ordering::Relaxed);
+                *self
+                    .metrics
+                    .last_error
+                    .write()
+                    .unwrap_or_else(|e| e.into_inner()) = Some((Instant::now(), e.to_string()));
+                Err(e.into())
+            }
+        }
+    }
+
     pub async fn send(&mut self, m: &str, p: &[Value]) -> Result<()> {
-        self.write
-            .lock()
+        self.send_message(SocketMessageSer::new(m, p).to_message()?)
             .await
-            .send(SocketMessageSer::new(m, p).to_message()?)
-            .await?;
-        Ok(())
+    }
+
+    /// Sends several messages in a single frame so nothing is interleaved between them.
+    pub async fn send_batch(&mut self, messages: &[SocketMessageSer]) -> Result<()> {
+        if messages.is_empty() {
+            return Ok(());
+        }
+        self.send_message(format_batch_packet(messages)?).await
     }
 
     pub async fn ping(&mut self, ping: &Message) -> Result<()> {
-        self.write.lock().await.send(ping.clone()).await?;
+        self.send_message(ping.clone()).await?;
         trace!("sent ping message {}", ping);
         Ok(())
     }
@@ -278,12 +487,10 @@ impl SocketSession {
     }
 
     pub async fn update_token(&mut self, auth_token: &str) -> Result<()> {
-        self.write
-            .lock()
-            .await
-            .send(SocketMessageSer::new("set_auth_token", payload!(auth_token)).to_message()?)
-            .await?;
-        Ok(())
+        self.send_message(
+            SocketMessageSer::new("set_auth_token", payload!(auth_token)).to_message()?,
+        )
+        .await
     }
 }
 
@@ -298,11 +505,36 @@ pub trait Socket {
                 Some(Ok(message)) => self.handle_raw_messages(session, message).await,
                 Some(Err(e)) => {
                     error!("Error reading message: {:#?}", e);
-                    self.handle_error(Error::WebSocketError(e)).await;
+                    self.handle_error(Error::from(e)).await;
                 }
                 None => {
-                    debug!("no messages to read");
-                    continue;
+                    debug!("connection closed, leaving event loop");
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Like [`Socket::event_loop`], but returns once no message arrived for `idle`
+    /// or the connection is closed.
+    async fn event_loop_until_idle(&mut self, session: &mut SocketSession, idle: Duration) {
+        let read = session.read.clone();
+        let mut read_guard = read.lock().await;
+        loop {
+            trace!("waiting for next message");
+            match tokio::time::timeout(idle, read_guard.next()).await {
+                Ok(Some(Ok(message))) => self.handle_raw_messages(session, message).await,
+                Ok(Some(Err(e))) => {
+                    error!("Error reading message: {:#?}", e);
+                    self.handle_error(Error::from(e)).await;
+                }
+                Ok(None) => {
+                    debug!("connection closed, leaving event loop");
+                    return;
+                }
+                Err(_) => {
+                    debug!("no messages received for {:?}, leaving event loop", idle);
+                    return;
                 }
             }
         }
@@ -312,7 +544,11 @@ pub trait Socket {
         match &raw {
             Message::Text(text) => {
                 trace!("parsing message: {:?}", text);
-                self.handle_parsed_messages(session, parse_packet(text), &raw)
+                let packet = parse_packet(text);
+                if packet.skipped > 0 {
+                    warn!("skipped {} corrupt frames", packet.skipped);
+                }
+                self.handle_parsed_messages(session, packet.messages, &raw)
                     .await;
             }
             Message::Close(msg) => {
@@ -322,6 +558,7 @@ pub trait Socket {
                 debug!("received binary message: {:?}", msg);
             }
             Message::Ping(msg) => {
+                // tungstenite queues the pong itself and flushes it on the next read
                 trace!("received ping message: {:?}", msg);
             }
             Message::Pong(msg) => {
@@ -371,3 +608,160 @@ pub trait Socket {
 
     async fn handle_error(&self, error: Error);
 }
+
+#[cfg(test)]
+pub(crate) mod testing {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    /// Starts a local websocket server that runs `handler` on every connection.
+    pub(crate) async fn mock_server<F, Fut>(handler: F) -> Url
+    where
+        F: Fn(WebSocketStream<TcpStream>) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+                tokio::spawn(handler(socket));
+            }
+        });
+        Url::parse(&format!("ws://{addr}")).unwrap()
+    }
+
+    /// Starts a local websocket server that runs `handler` on the first connection
+    /// and returns a session connected to it.
+    pub(crate) async fn mock_session<F, Fut>(handler: F) -> SocketSession
+    where
+        F: FnOnce(WebSocketStream<TcpStream>) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let handler = std::sync::Mutex::new(Some(handler));
+        let url = mock_server(move |socket| {
+            let handler = handler.lock().unwrap().take();
+            async move {
+                match handler {
+                    Some(handler) => handler(socket).await,
+                    None => drain(socket).await,
+                }
+            }
+        })
+        .await;
+        connect(url).await
+    }
+
+    /// Opens a session to a [`mock_server`].
+    pub(crate) async fn connect(url: Url) -> SocketSession {
+        SocketSession::open(
+            DataServer::Data,
+            url,
+            GUEST_AUTH_TOKEN.to_string(),
+            SocketConfig::default(),
+        )
+        .await
+        .unwrap()
+    }
+
+    /// Keeps the server side open until the client goes away.
+    pub(crate) async fn drain(mut socket: WebSocketStream<TcpStream>) {
+        while let Some(Ok(_)) = socket.next().await {}
+    }
+
+    /// Collects every message the client sends until it goes away.
+    pub(crate) async fn record(
+        mut socket: WebSocketStream<TcpStream>,
+        sink: tokio::sync::oneshot::Sender<Vec<SocketMessageDe>>,
+    ) {
+        let mut received = Vec::new();
+        while let Some(Ok(message)) = socket.next().await {
+            if let Message::Text(text) = message {
+                received.extend(
+                    parse_packet(&text)
+                        .messages
+                        .into_iter()
+                        .filter_map(|packet| match packet {
+                            SocketMessage::SocketMessage(m) => Some(m),
+                            _ => None,
+                        }),
+                );
+            }
+        }
+        sink.send(received).ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_socket_config_applies_to_stream() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let stream = TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+
+        SocketConfig::default().apply(&stream).unwrap();
+        assert!(!stream.nodelay().unwrap());
+        assert!(!SockRef::from(&stream).keepalive().unwrap());
+
+        let config = SocketConfig {
+            tcp_nodelay: true,
+            keepalive: Some(Duration::from_secs(15)),
+        };
+        config.apply(&stream).unwrap();
+        assert!(stream.nodelay().unwrap());
+        assert!(SockRef::from(&stream).keepalive().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_select_fastest_data_server() {
+        let probe = |server: DataServer| async move {
+            match server {
+                DataServer::Data => Ok(Duration::from_millis(80)),
+                DataServer::ProData => Ok(Duration::from_millis(20)),
+                _ => Err(Error::Generic("unreachable".to_string())),
+            }
+        };
+        let candidates = [
+            DataServer::Data,
+            DataServer::ProData,
+            DataServer::WidgetData,
+        ];
+        assert_eq!(
+            DataServer::select_fastest(&candidates, probe).await,
+            Some(DataServer::ProData)
+        );
+        assert_eq!(
+            DataServer::select_fastest(&[DataServer::WidgetData], probe).await,
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_send_is_recorded() {
+        let mut session = testing::mock_session(testing::drain).await;
+        session
+            .send("quote_create_session", &payload!("qs_1"))
+            .await
+            .unwrap();
+        assert_eq!(session.send_stats().sent, 1);
+        assert!(session.send_stats().last_error.is_none());
+
+        session.close().await.unwrap();
+        let before = Instant::now();
+        assert!(session
+            .send("quote_delete_session", &payload!("qs_1"))
+            .await
+            .is_err());
+
+        let stats = session.send_stats();
+        assert_eq!((stats.sent, stats.failed), (1, 1));
+        assert_eq!(stats.failure_rate(), 0.5);
+        let (at, message) = stats.last_error.unwrap();
+        assert!(at >= before);
+        assert!(!message.is_empty());
+    }
+}
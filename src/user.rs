@@ -23,6 +23,44 @@ impl UserCookies {
         }
     }
 
+    /// Builds cookies from a `Cookie` header copied from the browser, with or without the
+    /// `Cookie:` prefix. `sessionid` and `sessionid_sign` are required, `device_t` is used
+    /// when present. Other cookies are ignored.
+    pub fn from_cookie_header(header: &str) -> Result<Self> {
+        let header = header.trim();
+        let header = header
+            .split_once(':')
+            .filter(|(name, _)| name.trim().eq_ignore_ascii_case("cookie"))
+            .map_or(header, |(_, cookies)| cookies);
+
+        let mut user = UserCookies::new();
+        for cookie in header.split(';') {
+            let Some((name, value)) = cookie.split_once('=') else {
+                continue;
+            };
+            let value = value.trim().trim_matches('"').to_string();
+            match name.trim() {
+                "sessionid" => user.session = value,
+                "sessionid_sign" => user.session_signature = value,
+                "device_t" => user.device_token = value,
+                _ => {}
+            }
+        }
+
+        let missing: Vec<String> = [
+            ("sessionid", &user.session),
+            ("sessionid_sign", &user.session_signature),
+        ]
+        .into_iter()
+        .filter(|(_, value)| value.is_empty())
+        .map(|(name, _)| name.to_string())
+        .collect();
+        if !missing.is_empty() {
+            return Err(Error::LoginError(LoginError::MissingCookies(missing)));
+        }
+        Ok(user)
+    }
+
     pub async fn login(
         &mut self,
         username: &str,
@@ -222,3 +260,40 @@ impl UserCookies {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_cookie_header() {
+        let header = "Cookie: cookiePrivacyPreferenceBannerProduction=notApplicable; \
+            _ga=GA1.1.1234567890.1700000000; device_t=dGVzdDox.AbCd; \
+            sessionid=q8w9e7r6t5y4u3i2o1p0; sessionid_sign=v2:aGVsbG8=; tv_ecuid=abc-123";
+        let user = UserCookies::from_cookie_header(header).unwrap();
+        assert_eq!(user.session, "q8w9e7r6t5y4u3i2o1p0");
+        assert_eq!(user.session_signature, "v2:aGVsbG8=");
+        assert_eq!(user.device_token, "dGVzdDox.AbCd");
+
+        let user = UserCookies::from_cookie_header("sessionid=a;sessionid_sign=b").unwrap();
+        assert_eq!(
+            (user.session.as_str(), user.device_token.as_str()),
+            ("a", "")
+        );
+    }
+
+    #[test]
+    fn test_from_cookie_header_lists_missing_cookies() {
+        let err = UserCookies::from_cookie_header("device_t=x; _ga=y").unwrap_err();
+        assert_eq!(
+            std::error::Error::source(&err).unwrap().to_string(),
+            "missing cookies: sessionid, sessionid_sign"
+        );
+        match err {
+            Error::LoginError(LoginError::MissingCookies(missing)) => {
+                assert_eq!(missing, ["sessionid", "sessionid_sign"])
+            }
+            e => panic!("unexpected error {e:?}"),
+        }
+    }
+}
@@ -1,21 +1,21 @@
 use crate::{
-    models::{MarketAdjustment, SessionType},
+    models::{CurrencyOverride, MarketAdjustment, SessionType},
     socket::{SocketMessage, SocketMessageDe},
     Result, UserCookies,
 };
 use base64::engine::{general_purpose::STANDARD as BASE64, Engine as _};
-use iso_currency::Currency;
 use rand::Rng;
 use regex::Regex;
 use reqwest::{
-    header::{HeaderMap, HeaderValue, ACCEPT, COOKIE, ORIGIN, REFERER},
-    Response,
+    header::{HeaderMap, HeaderValue, ACCEPT, ACCEPT_ENCODING, COOKIE, ORIGIN, REFERER},
+    ClientBuilder, Response,
 };
 use serde::Serialize;
 use serde_json::Value;
 use std::{
     collections::HashMap,
     io::{prelude::*, Cursor},
+    time::Duration,
 };
 use tokio_tungstenite::tungstenite::protocol::Message;
 use tracing::{debug, error};
@@ -36,9 +36,26 @@ macro_rules! payload {
     };
 }
 
+/// Timeout of HTTP requests (symbol search, indicators, news, ...) that do not set their
+/// own. A request exceeding its timeout fails with `Error::Timeout`.
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
 pub fn build_request(cookie: Option<&str>) -> Result<reqwest::Client> {
+    let client = client_builder(cookie, DEFAULT_REQUEST_TIMEOUT)?
+        .https_only(true)
+        .build()?;
+    Ok(client)
+}
+
+/// Client configuration shared by all HTTP requests: TradingView headers,
+/// gzip/deflate/brotli response decompression and the request timeout.
+fn client_builder(cookie: Option<&str>, timeout: Duration) -> Result<ClientBuilder> {
     let mut headers = HeaderMap::new();
     headers.insert(ACCEPT, HeaderValue::from_static("application/json"));
+    headers.insert(
+        ACCEPT_ENCODING,
+        HeaderValue::from_static("gzip, deflate, br"),
+    );
     headers.insert(
         ORIGIN,
         HeaderValue::from_static("https://www.tradingview.com"),
@@ -51,13 +68,15 @@ pub fn build_request(cookie: Option<&str>) -> Result<reqwest::Client> {
         headers.insert(COOKIE, HeaderValue::from_str(cookie)?);
     }
 
-    let client = reqwest::Client::builder()
+    let builder = reqwest::Client::builder()
         .use_rustls_tls()
         .default_headers(headers)
-        .https_only(true)
-        .user_agent(crate::UA)
-        .build()?;
-    Ok(client)
+        .gzip(true)
+        .brotli(true)
+        .deflate(true)
+        .timeout(timeout)
+        .user_agent(crate::UA);
+    Ok(builder)
 }
 
 pub fn gen_session_id(session_type: &str) -> String {
@@ -74,29 +93,40 @@ pub fn gen_id() -> String {
     result
 }
 
-pub fn parse_packet(message: &str) -> Vec<SocketMessage<SocketMessageDe>> {
+/// The frames of one websocket message that could be parsed, and how many could not.
+#[derive(Debug, Default)]
+pub struct ParsedPacket {
+    pub messages: Vec<SocketMessage<SocketMessageDe>>,
+    pub skipped: usize,
+}
+
+/// Splits a `~m~len~m~payload` message into frames and parses each one on its own, so a
+/// corrupt frame is logged and skipped without losing the frames around it.
+pub fn parse_packet(message: &str) -> ParsedPacket {
+    let mut parsed = ParsedPacket::default();
     if message.is_empty() {
-        return vec![];
+        return parsed;
     }
 
     let cleaned_message = CLEANER_REGEX.replace_all(message, "");
-    let packets: Vec<SocketMessage<SocketMessageDe>> = SPLITTER_REGEX
+    for packet in SPLITTER_REGEX
         .split(&cleaned_message)
         .filter(|packet| !packet.is_empty())
-        .map(|packet| match serde_json::from_str(packet) {
-            Ok(value) => value,
+    {
+        match serde_json::from_str(packet) {
+            Ok(value) => parsed.messages.push(value),
             Err(error) => {
                 if error.is_syntax() {
-                    error!("error parsing packet, invalid JSON: {}", error);
+                    error!("skipping packet, invalid JSON: {}", error);
                 } else {
-                    error!("error parsing packet: {}", error);
+                    error!("skipping packet: {}", error);
                 }
-                SocketMessage::Unknown(packet.to_string())
+                parsed.skipped += 1;
             }
-        })
-        .collect();
+        }
+    }
 
-    packets
+    parsed
 }
 
 pub fn format_packet<T: Serialize>(packet: T) -> Result<Message> {
@@ -106,10 +136,21 @@ pub fn format_packet<T: Serialize>(packet: T) -> Result<Message> {
     Ok(Message::Text(formatted_message))
 }
 
+/// Formats several packets into a single websocket frame, preserving their order.
+pub fn format_batch_packet<T: Serialize>(packets: &[T]) -> Result<Message> {
+    let mut formatted_message = String::new();
+    for packet in packets {
+        let json_string = serde_json::to_string(packet)?;
+        formatted_message.push_str(&format!("~m~{}~m~{}", json_string.len(), json_string));
+    }
+    debug!("Formatted batch packet: {}", formatted_message);
+    Ok(Message::Text(formatted_message))
+}
+
 pub fn symbol_init(
     symbol: &str,
     adjustment: Option<MarketAdjustment>,
-    currency: Option<Currency>,
+    currency: Option<CurrencyOverride>,
     session_type: Option<SessionType>,
     replay: Option<String>,
 ) -> Result<String> {
@@ -117,7 +158,7 @@ pub fn symbol_init(
     if let Some(s) = replay {
         symbol_init.insert("replay".to_string(), s);
     }
-    if let Some(a) = adjustment {
+    if let Some(a) = adjustment.filter(|a| *a != MarketAdjustment::None) {
         symbol_init.insert("adjustment".to_string(), a.to_string());
     }
     symbol_init.insert("symbol".to_string(), symbol.to_string());
@@ -145,6 +186,17 @@ pub async fn get(
     client: Option<&UserCookies>,
     url: &str,
     queries: &[(&str, &str)],
+) -> Result<Response> {
+    get_with_timeout(client, url, queries, DEFAULT_REQUEST_TIMEOUT).await
+}
+
+/// Same as [`get`] but fails with `Error::Timeout` after `timeout` instead of
+/// [`DEFAULT_REQUEST_TIMEOUT`].
+pub async fn get_with_timeout(
+    client: Option<&UserCookies>,
+    url: &str,
+    queries: &[(&str, &str)],
+    timeout: Duration,
 ) -> Result<Response> {
     let c = match client {
         Some(client) => {
@@ -157,7 +209,7 @@ pub async fn get(
         None => build_request(None)?,
     };
 
-    let response = c.get(url).query(queries).send().await?;
+    let response = c.get(url).query(queries).timeout(timeout).send().await?;
     Ok(response)
 }
 
@@ -166,9 +218,107 @@ mod tests {
     use serde_json::json;
 
     use crate::{
-        models::{MarketAdjustment, SessionType},
+        models::{CurrencyOverride, MarketAdjustment, SessionType, SymbolSearchResponse},
+        socket::{SocketMessage, SocketMessageDe, SocketMessageSer},
         utils::*,
     };
+    #[test]
+    fn test_format_batch_packet_preserves_order() {
+        let packets = vec![
+            SocketMessageSer::new("quote_add_symbols", json!(["qs_1", "BINANCE:BTCUSDT"])),
+            SocketMessageSer::new("quote_add_symbols", json!(["qs_1", "NASDAQ:AAPL"])),
+            SocketMessageSer::new("quote_fast_symbols", json!(["qs_1", "NASDAQ:AAPL"])),
+        ];
+        let message = format_batch_packet(&packets).unwrap();
+        let parsed: Vec<SocketMessageDe> = parse_packet(&message.to_string())
+            .messages
+            .into_iter()
+            .map(|packet| match packet {
+                SocketMessage::SocketMessage(m) => m,
+                other => panic!("unexpected packet: {:?}", other),
+            })
+            .collect();
+        assert_eq!(parsed.len(), 3);
+        assert_eq!(parsed[0].m, "quote_add_symbols");
+        assert_eq!(parsed[0].p[1], "BINANCE:BTCUSDT");
+        assert_eq!(parsed[1].p[1], "NASDAQ:AAPL");
+        assert_eq!(parsed[2].m, "quote_fast_symbols");
+    }
+
+    #[tokio::test]
+    async fn test_brotli_response_is_decompressed() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let current_dir = std::env::current_dir().unwrap().display().to_string();
+        let body = std::fs::read(format!(
+            "{}/tests/data/symbol_search_response.json.br",
+            current_dir
+        ))
+        .unwrap();
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 1024];
+            while !request.ends_with(b"\r\n\r\n") {
+                let n = stream.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            let head = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-encoding: br\r\ncontent-length: {}\r\nconnection: close\r\n\r\n",
+                body.len()
+            );
+            stream.write_all(head.as_bytes()).await.unwrap();
+            stream.write_all(&body).await.unwrap();
+            String::from_utf8(request).unwrap().to_lowercase()
+        });
+
+        let response: SymbolSearchResponse = client_builder(None, DEFAULT_REQUEST_TIMEOUT)
+            .unwrap()
+            .build()
+            .unwrap()
+            .get(format!("http://{addr}/symbol_search/v3/"))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+
+        let request = server.await.unwrap();
+        assert!(request.contains("accept-encoding: gzip, deflate, br"));
+        assert_eq!(response.symbols.len(), 2);
+        assert_eq!(response.symbols[0].exchange, "NASDAQ");
+    }
+
+    #[tokio::test]
+    async fn test_request_timeout() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            // accept and never answer, not even the TLS handshake
+            let (stream, _) = listener.accept().await.unwrap();
+            tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+            drop(stream);
+        });
+
+        let started = std::time::Instant::now();
+        let result = get_with_timeout(
+            None,
+            &format!("https://{addr}/symbol_search/v3/"),
+            &[],
+            Duration::from_millis(200),
+        )
+        .await;
+        server.abort();
+
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        assert!(matches!(result, Err(crate::Error::Timeout(_))));
+    }
+
     #[test]
     fn test_parse_packet() {
         let current_dir = std::env::current_dir().unwrap().display().to_string();
@@ -178,8 +328,33 @@ mod tests {
                 .unwrap();
         let result = parse_packet(messages.as_str());
 
-        let data = result;
+        let data = result.messages;
         assert_eq!(data.len(), 42);
+        assert_eq!(result.skipped, 0);
+    }
+
+    #[test]
+    fn test_parse_packet_skips_corrupt_frames() {
+        let message = concat!(
+            r#"~m~33~m~{"m":"qsd","p":["qs_1",{"n":"A"}]}"#,
+            r#"~m~9~m~{"m":"qsd"#,
+            "~m~4~m~~h~1",
+            r#"~m~5~m~[1,2}"#,
+            r#"~m~33~m~{"m":"qsd","p":["qs_1",{"n":"B"}]}"#,
+        );
+        let result = parse_packet(message);
+
+        assert_eq!(result.skipped, 2);
+        let names: Vec<&str> = result
+            .messages
+            .iter()
+            .filter_map(|m| match m {
+                SocketMessage::SocketMessage(m) => m.p[1]["n"].as_str(),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(names, ["A", "B"]);
+        assert_eq!(result.messages.len(), 3);
     }
 
     #[test]
@@ -199,7 +374,7 @@ mod tests {
         let test2 = symbol_init(
             "HOSE:FPT",
             Some(MarketAdjustment::Dividends),
-            Some(iso_currency::Currency::USD),
+            Some(iso_currency::Currency::USD.into()),
             Some(SessionType::Extended),
             Some("aaaaaaaaaaaa".to_string()),
         );
@@ -213,5 +388,30 @@ mod tests {
             "symbol": "HOSE:FPT"
         });
         assert_eq!(test2_json, expected2_json);
+
+        let unadjusted = symbol_init(
+            "NASDAQ:AAPL",
+            Some(MarketAdjustment::None),
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        let unadjusted_json: Value = serde_json::from_str(&unadjusted.replace('=', "")).unwrap();
+        assert_eq!(unadjusted_json, json!({ "symbol": "NASDAQ:AAPL" }));
+
+        let crypto = symbol_init(
+            "BINANCE:BTCUSDT",
+            None,
+            Some(CurrencyOverride::from("USDT")),
+            None,
+            None,
+        )
+        .unwrap();
+        let crypto_json: Value = serde_json::from_str(&crypto.replace('=', "")).unwrap();
+        assert_eq!(
+            crypto_json,
+            json!({ "currency-id": "USDT", "symbol": "BINANCE:BTCUSDT" })
+        );
     }
 }
@@ -0,0 +1,26 @@
+use tradingview::prelude::*;
+
+#[test]
+fn test_prelude_builds_streaming_client() {
+    let callbacks = Callbacks::default()
+        .on_chart_data(
+            |(options, bars): (ChartOptions, Vec<DataPoint>)| async move {
+                ChartHistoricalData::new(options, bars).to_ohlcv();
+            },
+        )
+        .on_quote_data(|_: QuoteValue| async {})
+        .on_error(|_: Error| async {});
+    let client = WebSocketClient::default().set_callbacks(callbacks);
+
+    let _builder: WebSocketBuilder = WebSocket::new()
+        .server(DataServer::Data)
+        .tcp_nodelay(true)
+        .client(client);
+
+    let options = ChartOptions::new("BINANCE:BTCUSDT", Interval::OneMinute)
+        .bar_count(100)
+        .adjustment(MarketAdjustment::Splits)
+        .session_type(SessionType::Regular)
+        .currency("USDT");
+    assert!(options.validate().is_ok());
+}